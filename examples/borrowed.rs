@@ -0,0 +1,46 @@
+//! Compares the parse time of a large `user.ratedList` result into owned
+//! [`User`]s and into borrowed [`UserRef`]s.
+//!
+//! Run with `cargo run --release --example borrowed`.
+use codeforces::borrowed::UserRef;
+use codeforces::User;
+use std::time::Instant;
+
+const USERS: usize = 100_000;
+const ROUNDS: u32 = 10;
+
+fn main() {
+    let users: Vec<String> = (0..USERS)
+        .map(|i| {
+            format!(
+                r#"{{"handle": "user_{i}", "firstName": "First{i}", "lastName": "Last{i}",
+                "country": "Vietnam", "city": "Hanoi", "organization": "University {i}",
+                "contribution": 0, "rank": "expert", "maxRank": "candidate master",
+                "rating": 1800, "maxRating": 1950, "lastOnlineTimeSeconds": 1600000000,
+                "registrationTimeSeconds": 1400000000, "friendOfCount": 12,
+                "avatar": "//userpic.codeforces.org/no-avatar.jpg",
+                "titlePhoto": "//userpic.codeforces.org/no-title.jpg"}}"#,
+                i = i
+            )
+        })
+        .collect();
+    let body = format!("[{}]", users.join(","));
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let users: Vec<User> = serde_json::from_str(&body).unwrap();
+        assert_eq!(users.len(), USERS);
+    }
+    let owned = start.elapsed() / ROUNDS;
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let users: Vec<UserRef> = serde_json::from_str(&body).unwrap();
+        assert_eq!(users.len(), USERS);
+    }
+    let borrowed = start.elapsed() / ROUNDS;
+
+    println!("{} users, {} bytes", USERS, body.len());
+    println!("owned:    {:?}", owned);
+    println!("borrowed: {:?}", borrowed);
+}
@@ -0,0 +1,46 @@
+//! Matching on the crate's enums from downstream code.
+//!
+//! `Verdict`, `ContestPhase`, `ParticipantType` and `ContestType` are
+//! `#[non_exhaustive]`, since Codeforces adds new values over time: matches on
+//! them need a wildcard arm, which also handles values added in later versions.
+use codeforces::{ContestPhase, ContestType, ParticipantType, Verdict};
+
+fn verdict(verdict: Verdict) -> &'static str {
+    match verdict {
+        Verdict::Ok => "accepted",
+        Verdict::Testing => "in queue",
+        Verdict::WrongAnswer | Verdict::TimeLimitExceeded => "rejected",
+        _ => "other",
+    }
+}
+
+fn phase(phase: ContestPhase) -> &'static str {
+    match phase {
+        ContestPhase::Before => "upcoming",
+        ContestPhase::Coding => "running",
+        _ => "over",
+    }
+}
+
+fn participant(participant_type: ParticipantType) -> &'static str {
+    match participant_type {
+        ParticipantType::Contestant => "official",
+        ParticipantType::Virtual | ParticipantType::Practice => "unofficial",
+        _ => "other",
+    }
+}
+
+fn contest_type(contest_type: ContestType) -> &'static str {
+    match contest_type {
+        ContestType::CF => "points",
+        ContestType::ICPC => "penalty",
+        _ => "other",
+    }
+}
+
+fn main() {
+    println!("{}", verdict(Verdict::Ok));
+    println!("{}", phase(ContestPhase::Finished));
+    println!("{}", participant(ParticipantType::Practice));
+    println!("{}", contest_type(ContestType::IOI));
+}
@@ -0,0 +1,25 @@
+//! Testing code built on top of the API without hitting the network.
+//!
+//! Run with `cargo run --example mock --features test-util`.
+use codeforces::{Client, User};
+use std::collections::HashMap;
+
+#[tokio::main]
+async fn main() {
+    let mut responses = HashMap::new();
+    responses.insert(
+        "user.info".to_owned(),
+        r#"{"status": "OK", "result": [{
+            "handle": "tourist", "rating": 3800, "rank": "legendary grandmaster",
+            "contribution": 0, "lastOnlineTimeSeconds": 1600000000,
+            "registrationTimeSeconds": 1265987288, "friendOfCount": 60000,
+            "avatar": "//userpic.codeforces.org/no-avatar.jpg",
+            "titlePhoto": "//userpic.codeforces.org/no-title.jpg"
+        }]}"#
+            .to_owned(),
+    );
+    let client = Client::mock(responses);
+
+    let users = User::info(&client, &["tourist"]).await.unwrap();
+    println!("{} is rated {:?}", users[0].handle, users[0].rating);
+}
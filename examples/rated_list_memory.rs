@@ -0,0 +1,80 @@
+//! Compares the peak memory of finding the top users of a large
+//! `user.ratedList` response with [`User::rated_list`] and with
+//! [`User::rated_list_stream`].
+//!
+//! Run with `cargo run --release --example rated_list_memory --features test-util`.
+use codeforces::{Client, User};
+use futures_util::StreamExt;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Tracks the current and peak number of allocated bytes.
+struct Counting;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let now = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(now, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOC: Counting = Counting;
+
+const USERS: usize = 100_000;
+
+/// The peak memory allocated by `f` on top of what was allocated before, in MB.
+async fn peak_mb<F: std::future::Future<Output = usize>>(f: F) -> (usize, f64) {
+    let base = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(base, Ordering::Relaxed);
+    let kept = f.await;
+    let peak = PEAK.load(Ordering::Relaxed) - base;
+    (kept, peak as f64 / 1e6)
+}
+
+#[tokio::main]
+async fn main() {
+    let users: Vec<String> = (0..USERS)
+        .map(|i| {
+            format!(
+                r#"{{"handle":"user_{i}","firstName":"First{i}","lastName":"Last{i}","country":"Vietnam","city":"Hanoi","organization":"University {i}","contribution":0,"rank":"expert","maxRank":"master","rating":{r},"maxRating":{r},"lastOnlineTimeSeconds":1600000000,"registrationTimeSeconds":1400000000,"friendOfCount":12,"avatar":"//userpic.codeforces.org/no-avatar.jpg","titlePhoto":"//userpic.codeforces.org/no-title.jpg"}}"#,
+                i = i,
+                r = 1000 + i % 2500
+            )
+        })
+        .collect();
+    let body = format!(r#"{{"status":"OK","result":[{}]}}"#, users.join(","));
+    println!("{} users, {:.1}MB response", USERS, body.len() as f64 / 1e6);
+    drop(users);
+
+    let mut responses = HashMap::new();
+    responses.insert("user.ratedList".to_owned(), body);
+    let client = Client::mock(responses);
+    let is_top = |u: &User| u.rating.unwrap_or(0) >= 3400;
+
+    let (kept, peak) = peak_mb(async {
+        let users = User::rated_list(&client, false).await.unwrap();
+        users.into_iter().filter(is_top).count()
+    })
+    .await;
+    println!("rated_list:        kept {}, peak {:.1}MB", kept, peak);
+
+    let (kept, peak) = peak_mb(async {
+        User::rated_list_stream(&client, false)
+            .filter(|u| std::future::ready(matches!(u, Ok(u) if is_top(u))))
+            .count()
+            .await
+    })
+    .await;
+    println!("rated_list_stream: kept {}, peak {:.1}MB", kept, peak);
+}
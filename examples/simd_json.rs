@@ -0,0 +1,39 @@
+//! Compares the parse time of a large `user.ratedList` response with
+//! `serde_json` and with `simd-json`, as used by [`User::rated_list`].
+//!
+//! Run with `cargo run --release --example simd_json --features simd-json`.
+use codeforces::{decode_bulk_response, decode_response, User};
+use std::time::Instant;
+
+const USERS: usize = 100_000;
+const ROUNDS: u32 = 10;
+
+fn main() {
+    let users: Vec<String> = (0..USERS)
+        .map(|i| {
+            format!(
+                r#"{{"handle":"user_{i}","firstName":"First{i}","lastName":"Last{i}","country":"Vietnam","city":"Hanoi","organization":"University {i}","contribution":0,"rank":"expert","maxRank":"candidate master","rating":1800,"maxRating":1950,"lastOnlineTimeSeconds":1600000000,"registrationTimeSeconds":1400000000,"friendOfCount":12,"avatar":"//userpic.codeforces.org/no-avatar.jpg","titlePhoto":"//userpic.codeforces.org/no-title.jpg"}}"#,
+                i = i
+            )
+        })
+        .collect();
+    let body = format!(r#"{{"status":"OK","result":[{}]}}"#, users.join(","));
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let users: Vec<User> = decode_response(body.as_bytes()).unwrap();
+        assert_eq!(users.len(), USERS);
+    }
+    let serde_json = start.elapsed() / ROUNDS;
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let users: Vec<User> = decode_bulk_response(body.as_bytes()).unwrap();
+        assert_eq!(users.len(), USERS);
+    }
+    let simd_json = start.elapsed() / ROUNDS;
+
+    println!("{} users, {} bytes", USERS, body.len());
+    println!("serde_json: {:?}", serde_json);
+    println!("simd-json:  {:?}", simd_json);
+}
@@ -0,0 +1,24 @@
+//! Fetches a user from the browser, printing it to the console.
+//!
+//! Build with `cargo build --example wasm --target wasm32-unknown-unknown`,
+//! then generate the JavaScript bindings with
+//! `wasm-bindgen --target web target/wasm32-unknown-unknown/debug/examples/wasm.wasm`
+//! and load them from a page.
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    use codeforces::{Client, User};
+
+    wasm_bindgen_futures::spawn_local(async {
+        let client = Client::new();
+        let message = match User::info(&client, &["tourist"]).await {
+            Ok(users) => users[0].to_string(),
+            Err(e) => format!("user.info failed: {}", e),
+        };
+        web_sys::console::log_1(&message.into());
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    eprintln!("This example runs in the browser, build it for wasm32-unknown-unknown.");
+}
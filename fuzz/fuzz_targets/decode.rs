@@ -0,0 +1,15 @@
+//! Feeds arbitrary bytes to the decoding of API responses.
+//! Decoding may fail, but must never panic.
+//!
+//! Run with `cargo fuzz run decode` from the repository root.
+#![no_main]
+use codeforces::{Contest, Standings, Submission};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = serde_json::from_slice::<Vec<Submission>>(data);
+    let _ = serde_json::from_slice::<Contest>(data);
+    let _ = serde_json::from_slice::<Standings>(data);
+    let _ = codeforces::decode_response::<Vec<Submission>>(data);
+    let _ = codeforces::decode_response::<Contest>(data);
+});
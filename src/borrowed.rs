@@ -0,0 +1,133 @@
+//! Borrowed variants of the hottest types, for parsing very large responses
+//! such as `user.ratedList` without copying every string.
+//!
+//! Strings are `Cow<'a, str>` borrowing from the response body: they are only
+//! allocated when the JSON string has escape sequences, which is rare for
+//! handles and names. The borrowed values cannot outlive the body, so this is
+//! meant for callers that process a response and drop it; use `.into()` or
+//! `into_owned` to keep values around.
+//!
+//! Parsing a `user.ratedList` response of 100000 users takes about 30% less
+//! time than with [`User`] (see `examples/borrowed.rs`).
+use super::*;
+use std::borrow::Cow;
+
+/// Decodes a raw API response into borrowed values.
+///
+/// The response is typically fetched by hand, e.g. with reqwest, or read
+/// back from disk.
+pub fn decode<'a, T: Deserialize<'a>>(body: &'a str) -> Result<T> {
+    #[derive(Deserialize)]
+    #[serde(bound(deserialize = "T: Deserialize<'de>"))]
+    struct Envelope<T> {
+        result: Option<T>,
+        comment: Option<String>,
+    }
+
+    let envelope: Envelope<T> = serde_json::from_str(body)?;
+    match envelope.result {
+        Some(v) => Ok(v),
+        None => Err(envelope_error(envelope.comment)),
+    }
+}
+
+/// The borrowed variant of [`User`].
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserRef<'a> {
+    #[serde(borrow)]
+    pub handle: Cow<'a, str>,
+    #[serde(borrow)]
+    pub email: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub first_name: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub last_name: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub country: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub organization: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub city: Option<Cow<'a, str>>,
+    pub contribution: i64,
+    #[serde(borrow)]
+    pub rank: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub max_rank: Option<Cow<'a, str>>,
+    pub rating: Option<i64>,
+    pub max_rating: Option<i64>,
+    pub last_online_time_seconds: Option<u64>,
+    pub registration_time_seconds: u64,
+    pub friend_of_count: u64,
+    #[serde(borrow)]
+    pub avatar: Cow<'a, str>,
+    #[serde(borrow)]
+    pub title_photo: Cow<'a, str>,
+}
+
+/// The borrowed variant of [`RatingChange`].
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RatingChangeRef<'a> {
+    pub contest_id: u64,
+    #[serde(borrow)]
+    pub contest_name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub handle: Cow<'a, str>,
+    pub rank: u64,
+    pub rating_update_time_seconds: u64,
+    pub old_rating: i64,
+    pub new_rating: i64,
+}
+
+impl UserRef<'_> {
+    /// Copies the borrowed strings into a [`User`].
+    pub fn into_owned(self) -> User {
+        self.into()
+    }
+}
+
+impl RatingChangeRef<'_> {
+    /// Copies the borrowed strings into a [`RatingChange`].
+    pub fn into_owned(self) -> RatingChange {
+        self.into()
+    }
+}
+
+impl From<UserRef<'_>> for User {
+    fn from(u: UserRef<'_>) -> Self {
+        User {
+            handle: u.handle.into_owned(),
+            email: u.email.map(Cow::into_owned),
+            first_name: u.first_name.map(Cow::into_owned),
+            last_name: u.last_name.map(Cow::into_owned),
+            country: u.country.map(Cow::into_owned),
+            organization: u.organization.map(Cow::into_owned),
+            city: u.city.map(Cow::into_owned),
+            contribution: u.contribution,
+            rank: u.rank.map(Cow::into_owned),
+            max_rank: u.max_rank.map(Cow::into_owned),
+            rating: u.rating,
+            max_rating: u.max_rating,
+            last_online_time_seconds: u.last_online_time_seconds,
+            registration_time_seconds: u.registration_time_seconds,
+            friend_of_count: u.friend_of_count,
+            avatar: u.avatar.into_owned(),
+            title_photo: u.title_photo.into_owned(),
+        }
+    }
+}
+
+impl From<RatingChangeRef<'_>> for RatingChange {
+    fn from(r: RatingChangeRef<'_>) -> Self {
+        RatingChange {
+            contest_id: r.contest_id,
+            contest_name: r.contest_name.into_owned(),
+            handle: r.handle.into_owned(),
+            rank: r.rank,
+            rating_update_time_seconds: r.rating_update_time_seconds,
+            old_rating: r.old_rating,
+            new_rating: r.new_rating,
+        }
+    }
+}
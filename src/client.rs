@@ -1,71 +1,1004 @@
+use crate::rt::{self, Instant, SystemTime, UNIX_EPOCH};
+use crate::standings::StandingsCache;
+use crate::{CFResult, Error, Result, Standings};
 use reqwest::Client as HTTP;
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// How many times a request is retried by default when Codeforces reports
+/// that the call limit is exceeded.
+const CALL_LIMIT_RETRIES: usize = 3;
+
+/// The default base URL of all API methods.
+const API_URL: &str = "https://codeforces.com/api";
+
+/// The codeforces API require only 1 request per two seconds.
+const RATE_LIMIT: (usize, Duration) = (1, Duration::from_secs(2));
+
 /// Client represents a Codeforces API client.
 /// It wraps around a reqwest HTTP client and provides rate-limiting.
-pub struct Client(rate_limit::Ratelimit<HTTP>);
+///
+/// Clones are cheap and share everything with the original: the rate limit,
+/// the concurrency limit, the caches and the shutdown state. Clone a Client
+/// to use it from several tasks.
+#[derive(Clone)]
+pub struct Client {
+    http: HTTP,
+    /// Like `http`, but never follows redirects.
+    #[cfg(all(feature = "scrape", not(target_arch = "wasm32")))]
+    no_redirect: HTTP,
+    limit: Arc<rate_limit::Ratelimit<()>>,
+    /// The base URL of API methods, without a trailing slash.
+    base_url: String,
+    /// Difference between the server clock and the local clock, in seconds.
+    time_offset: Arc<AtomicI64>,
+    /// Total time spent waiting for the rate limit, in nanoseconds.
+    waited: Arc<AtomicU64>,
+    /// Caps the number of requests in flight, if set.
+    concurrency: Option<Arc<Semaphore>>,
+    /// Caches the standings of finished contests, if set.
+    standings_cache: Option<Arc<StandingsCache>>,
+    /// How many times a request exceeding the call limit is retried.
+    call_limit_retries: usize,
+    /// The language of the responses, if set.
+    lang: Option<String>,
+    counters: Arc<Counters>,
+    /// Called before each API call, if set.
+    on_request: Option<RequestHook>,
+    /// Sends the API calls in place of `http`, if set.
+    #[cfg(feature = "middleware")]
+    middleware: Option<reqwest_middleware::ClientWithMiddleware>,
+    #[cfg(any(test, feature = "test-util"))]
+    mock: Option<Arc<HashMap<String, String>>>,
+}
+
+/// A callback given the method and the redacted parameters of each call.
+type RequestFn = dyn Fn(&str, &[(String, String)]) + Send + Sync;
+
+#[derive(Clone)]
+struct RequestHook(Arc<RequestFn>);
+
+impl std::fmt::Debug for RequestHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RequestHook")
+    }
+}
 
 impl Client {
-    /// New creates a new Client.
+    /// New creates a new Client with the default options.
+    /// Use [`Client::builder`] to configure one.
     pub fn new() -> Self {
-        Self(rate_limit::Ratelimit::new(
-            HTTP::new(),
-            // The codeforces API require only 1 request per two seconds
-            1,
-            std::time::Duration::from_secs(2),
-        ))
+        Self::builder()
+            .build()
+            .expect("failed to build the default Client")
+    }
+
+    /// Creates a [`ClientBuilder`] to configure a Client.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    /// Creates a Client sending requests to another base URL, e.g. a mirror
+    /// (`https://codeforc.es/api`) or a local proxy.
+    ///
+    /// Methods are called at `{url}/{method}`, so `url` should be the equivalent
+    /// of `https://codeforces.com/api`.
+    ///
+    /// A shortcut for [`ClientBuilder::base_url`] with the other options left
+    /// to their defaults.
+    pub fn with_base_url(url: String) -> Self {
+        Self {
+            base_url: url.trim_end_matches('/').to_owned(),
+            ..Self::new()
+        }
+    }
+
+    /// Creates a Client with at most `limit` requests in flight at once.
+    ///
+    /// This applies on top of the rate limit: a request waits for both a free
+    /// slot and the rate limit. A shortcut for [`ClientBuilder::concurrency_limit`]
+    /// with the other options left to their defaults.
+    ///
+    /// # Panics
+    ///
+    /// If `limit` is 0, which would never allow a request.
+    pub fn with_concurrency_limit(limit: usize) -> Self {
+        assert!(
+            limit > 0,
+            "concurrency limit must allow at least one request"
+        );
+        Self {
+            concurrency: Some(Arc::new(Semaphore::new(limit))),
+            ..Self::new()
+        }
+    }
+
+    /// The base URL that API methods are called on.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// The URL of the API method `method`.
+    fn method_url(&self, method: &str) -> String {
+        format!("{}/{}", self.base_url, method)
+    }
+
+    /// Creates a Client that never hits the network, answering requests with
+    /// the given canned JSON responses instead.
+    ///
+    /// Responses are keyed either by the full request URL
+    /// (e.g. `https://codeforces.com/api/user.info?handles=tourist`), or by the
+    /// method name alone (e.g. `user.info`). The full URL takes precedence.
+    /// Requests without a matching response fail with [`Error::Local`].
+    ///
+    /// Mock clients are not rate-limited.
+    ///
+    /// Only available with the `test-util` feature.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn mock(responses: HashMap<String, String>) -> Self {
+        Self {
+            mock: Some(Arc::new(responses)),
+            ..Self::new()
+        }
     }
 
     /// Borrows and returns the inner HTTP client.
-    pub(crate) async fn borrow<'a>(&'a self) -> impl std::ops::Deref<Target = HTTP> + 'a {
-        self.0.borrow().await
+    ///
+    /// Waits for a free slot under the concurrency limit first, then for the
+    /// rate limit. Both are held until the returned reference is dropped.
+    pub(crate) async fn borrow<'a>(&'a self) -> Result<impl Deref<Target = HTTP> + 'a> {
+        let permit = match self.concurrency {
+            Some(ref c) => Some(c.acquire().await.map_err(|_| Error::Shutdown)?),
+            None => None,
+        };
+        let start = Instant::now();
+        let limit = self.limit.borrow().await.ok_or(Error::Shutdown);
+        self.waited
+            .fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        Ok(Borrowed {
+            http: &self.http,
+            _limit: limit?,
+            _permit: permit,
+        })
+    }
+
+    /// Borrows the inner HTTP client like API calls do, under the
+    /// concurrency and rate limits, e.g. to send requests by hand.
+    ///
+    /// Fails with [`Error::RateLimitTimeout`] if no slot frees up within
+    /// `timeout`, in which case no slot is used up.
+    pub async fn try_borrow_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<impl Deref<Target = HTTP> + '_> {
+        rt::timeout(timeout, self.borrow())
+            .await
+            .ok_or(Error::RateLimitTimeout)?
+    }
+
+    /// The total time this client spent waiting for the rate limit, including
+    /// the waits before retrying requests that exceeded the call limit.
+    ///
+    /// Compare the value before and after a chain of calls to know how much of
+    /// it was spent waiting.
+    pub fn total_wait(&self) -> Duration {
+        Duration::from_nanos(self.waited.load(Ordering::Relaxed))
+    }
+
+    /// A snapshot of the counters of requests made by this client and its
+    /// clones.
+    pub fn metrics(&self) -> Metrics {
+        self.counters.snapshot()
+    }
+
+    /// Shuts down the client, along with all its clones, and the clients
+    /// sharing its rate limit through a [`RateLimitRegistry`].
+    ///
+    /// Requests made after (or still waiting for the rate limit during) the
+    /// shutdown fail with [`Error::Shutdown`]. This returns once all requests
+    /// in flight have completed.
+    pub async fn shutdown(&self) {
+        if let Some(ref c) = self.concurrency {
+            c.close();
+        }
+        self.limit.close().await
+    }
+
+    /// Fetches the current time of the Codeforces server, in seconds since the
+    /// Unix epoch, from the `Date` header of a cheap request.
+    ///
+    /// The offset between the server clock and the local clock is remembered
+    /// and used by [`Client::server_now`].
+    pub async fn server_time(&self) -> Result<u64> {
+        #[cfg(any(test, feature = "test-util"))]
+        if self.mock.is_some() {
+            return Ok(self.server_now());
+        }
+
+        let resp = self
+            .borrow()
+            .await?
+            .head(self.method_url("user.info"))
+            .send()
+            .await?;
+        let date = resp
+            .headers()
+            .get(reqwest::header::DATE)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| Error::Local("missing Date header".to_owned()))?;
+        let offset = time_offset(date, local_now())?;
+        self.time_offset.store(offset, Ordering::Relaxed);
+        Ok((local_now() as i64 + offset) as u64)
+    }
+
+    /// The estimated current time of the Codeforces server, in seconds since
+    /// the Unix epoch: the local time, corrected by the offset found by the
+    /// last call to [`Client::server_time`].
+    pub fn server_now(&self) -> u64 {
+        (local_now() as i64 + self.time_offset.load(Ordering::Relaxed)) as u64
+    }
+
+    /// Finds the handle that the profile page of `handle` redirects to, if
+    /// any, on the website the API is served from.
+    ///
+    /// Returns `None` without a redirect to another profile, e.g. for handles
+    /// that never existed. Mock clients never find a redirect.
+    #[cfg(feature = "scrape")]
+    pub(crate) async fn profile_redirect(&self, handle: &str) -> Result<Option<String>> {
+        #[cfg(any(test, feature = "test-util"))]
+        if self.mock.is_some() {
+            return Ok(None);
+        }
+
+        let site = self.base_url.strip_suffix("/api").unwrap_or(&self.base_url);
+        let url = format!("{}/profile/{}", site, handle);
+        // The shared client follows redirects, so the request goes through
+        // another one, still under the rate limit.
+        #[cfg(not(target_arch = "wasm32"))]
+        let location = {
+            let _borrowed = self.borrow().await?;
+            let resp = self.no_redirect.get(&url).send().await?;
+            resp.headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|l| resp.url().join(l).ok())
+        };
+        // The browser always follows redirects, ending up on the new profile.
+        #[cfg(target_arch = "wasm32")]
+        let location = Some(self.borrow().await?.get(&url).send().await?.url().clone());
+        let renamed = location.and_then(|l| {
+            let mut path = l.path_segments()?;
+            match (path.next(), path.next(), path.next()) {
+                (Some("profile"), Some(new), None) if !new.is_empty() => Some(new.to_owned()),
+                _ => None,
+            }
+        });
+        Ok(renamed.filter(|new| new != handle))
+    }
+
+    /// Calls the API method `method` with the given query parameters.
+    ///
+    /// Requests exceeding the call limit are retried after the next rate-limit
+    /// wait, up to [`CALL_LIMIT_RETRIES`] times by default
+    /// (see [`ClientBuilder::call_limit_retries`]).
+    ///
+    /// Errors sent back from codeforces carry the method and parameters.
+    pub(crate) async fn get<T>(&self, method: &str, query: &[(&str, String)]) -> Result<T>
+    where
+        T: for<'t> Deserialize<'t>,
+    {
+        self.get_with(method, query, |raw| crate::decode_response(&raw))
+            .await
+    }
+
+    /// Like [`Client::get`], but decodes the raw response with `decode`,
+    /// which also reports the errors sent back from codeforces.
+    pub(crate) async fn get_with<T>(
+        &self,
+        method: &str,
+        query: &[(&str, String)],
+        decode: impl Fn(Vec<u8>) -> Result<T>,
+    ) -> Result<T> {
+        if let Some(RequestHook(ref hook)) = self.on_request {
+            hook(method, &crate::redact_params(query));
+        }
+        let mut retries = 0;
+        let v = loop {
+            self.counters.requests.fetch_add(1, Ordering::Relaxed);
+            let v = match self.fetch(method, query).await {
+                Ok(raw) => decode(raw),
+                Err(e) => Err(e),
+            };
+            match v {
+                Err(ref e) if e.is_call_limit_exceeded() && retries < self.call_limit_retries => {
+                    self.counters.retries.fetch_add(1, Ordering::Relaxed);
+                    retries += 1
+                }
+                v => break v,
+            }
+        };
+        self.counters.record(&v);
+        v.map_err(|e| e.with_call(method, query))
+    }
+
+    /// Calls `contest.standings` with the given query parameters.
+    ///
+    /// With the standings cache enabled, standings of finished contests are
+    /// returned from the cache. Standings of other phases are always fetched.
+    pub(crate) async fn get_standings(&self, query: &[(&str, String)]) -> Result<Standings> {
+        let method = "contest.standings";
+        let cache = match self.standings_cache {
+            Some(ref cache) => cache,
+            None => {
+                return self
+                    .get_with(method, query, |raw| crate::decode_bulk_response(&raw))
+                    .await
+            }
+        };
+        // Mirrors serve the same standings, so the base URL is left out. The
+        // language changes the contest and problem names, so it is kept.
+        let lang = self.lang.iter().map(|l| ("lang", l.clone()));
+        let query_string: Vec<_> = query
+            .iter()
+            .cloned()
+            .chain(lang)
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+        let key = format!("{}?{}", method, query_string.join("&"));
+        if let Some(standings) = cache.get(&key).await {
+            return Ok(standings);
+        }
+        let (standings, raw) = self
+            .get_with(method, query, |raw| {
+                crate::decode_bulk_response(&raw).map(|s| (s, raw))
+            })
+            .await?;
+        cache.insert(&key, &standings, &raw).await;
+        Ok(standings)
+    }
+
+    /// Calls the API method `method` once, returning the raw response.
+    async fn fetch(&self, method: &str, query: &[(&str, String)]) -> Result<Vec<u8>> {
+        let lang: Vec<_> = self.lang.iter().map(|l| ("lang", l)).collect();
+        #[cfg(any(test, feature = "test-util"))]
+        if let Some(ref mock) = self.mock {
+            let url = self
+                .http
+                .get(self.method_url(method))
+                .query(query)
+                .query(&lang)
+                .build()?;
+            let body = mock
+                .get(url.url().as_str())
+                .or_else(|| mock.get(method))
+                .ok_or_else(|| Error::Local(format!("no mock response for {}", url.url())))?;
+            return Ok(body.clone().into_bytes());
+        }
+
+        let http = self.borrow().await?;
+        let url = self.method_url(method);
+        #[cfg(feature = "middleware")]
+        let resp = match self.middleware {
+            Some(ref m) => m.get(url).query(query).query(&lang).send().await?,
+            None => http.get(url).query(query).query(&lang).send().await?,
+        };
+        #[cfg(not(feature = "middleware"))]
+        let resp = http.get(url).query(query).query(&lang).send().await?;
+        // Codeforces answers failed calls with an error status, but the body
+        // still holds the comment: only fall back to the status without one.
+        let status = resp.error_for_status_ref().err();
+        let body = resp.bytes().await?;
+        match status {
+            Some(e) if serde_json::from_slice::<CFResult<IgnoredAny>>(&body).is_err() => {
+                Err(e.into())
+            }
+            _ => Ok(body.to_vec()),
+        }
+    }
+}
+
+/// Counters of the API calls made by a [`Client`], see [`Client::metrics`].
+///
+/// A call may send several requests when it is retried. Calls answered from a
+/// cache are not counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Metrics {
+    /// Requests sent, including retries.
+    pub requests: u64,
+    /// Retries after exceeding the call limit.
+    pub retries: u64,
+    /// Calls that succeeded.
+    pub succeeded: u64,
+    /// Calls that failed with [`Error::Http`].
+    pub http_errors: u64,
+    /// Calls that failed with [`Error::Decode`].
+    pub decode_errors: u64,
+    /// Calls that failed with [`Error::Codeforces`].
+    pub codeforces_errors: u64,
+    /// Calls that failed with [`Error::Forbidden`].
+    pub forbidden_errors: u64,
+    /// Calls that failed with [`Error::Shutdown`].
+    pub shutdown_errors: u64,
+    /// Calls that failed with [`Error::Middleware`].
+    pub middleware_errors: u64,
+    /// Calls that failed with [`Error::Local`], e.g. without a mock response.
+    pub local_errors: u64,
+}
+
+/// The live counterpart of [`Metrics`].
+#[derive(Default)]
+struct Counters {
+    requests: AtomicU64,
+    retries: AtomicU64,
+    succeeded: AtomicU64,
+    http_errors: AtomicU64,
+    decode_errors: AtomicU64,
+    codeforces_errors: AtomicU64,
+    forbidden_errors: AtomicU64,
+    shutdown_errors: AtomicU64,
+    middleware_errors: AtomicU64,
+    local_errors: AtomicU64,
+}
+
+impl Counters {
+    /// Counts the outcome of a call.
+    fn record<T>(&self, result: &Result<T>) {
+        let counter = match result {
+            Ok(_) => &self.succeeded,
+            Err(Error::Http(_)) => &self.http_errors,
+            Err(Error::Decode(_)) => &self.decode_errors,
+            Err(Error::Codeforces(_)) => &self.codeforces_errors,
+            Err(Error::Local(_)) => &self.local_errors,
+            Err(Error::Forbidden(_)) => &self.forbidden_errors,
+            Err(Error::Shutdown) => &self.shutdown_errors,
+            Err(Error::Middleware(_)) => &self.middleware_errors,
+            // Only returned when borrowing by hand, never by calls.
+            Err(Error::RateLimitTimeout) => return,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> Metrics {
+        let load = |c: &AtomicU64| c.load(Ordering::Relaxed);
+        Metrics {
+            requests: load(&self.requests),
+            retries: load(&self.retries),
+            succeeded: load(&self.succeeded),
+            http_errors: load(&self.http_errors),
+            decode_errors: load(&self.decode_errors),
+            codeforces_errors: load(&self.codeforces_errors),
+            forbidden_errors: load(&self.forbidden_errors),
+            shutdown_errors: load(&self.shutdown_errors),
+            middleware_errors: load(&self.middleware_errors),
+            local_errors: load(&self.local_errors),
+        }
+    }
+}
+
+/// The HTTP client borrowed from a [`Client`].
+///
+/// Fields are dropped in order: the rate limit clock starts before the
+/// concurrency slot is freed.
+struct Borrowed<'a, G> {
+    http: &'a HTTP,
+    _limit: G,
+    _permit: Option<SemaphorePermit<'a>>,
+}
+
+impl<'a, G> Deref for Borrowed<'a, G> {
+    type Target = HTTP;
+    fn deref(&self) -> &HTTP {
+        self.http
+    }
+}
+
+/// The local time, in seconds since the Unix epoch.
+fn local_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Computes the offset of the server clock, given in an HTTP `Date` header,
+/// from the local time `local_seconds`.
+pub(crate) fn time_offset(date: &str, local_seconds: u64) -> Result<i64> {
+    let server = httpdate::parse_http_date(date)
+        .map_err(|_| Error::Local(format!("invalid Date header: {}", date)))?
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Ok(server as i64 - local_seconds as i64)
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builds a [`Client`] with custom settings: base URL, language, timeout,
+/// retries, rate and concurrency limits, caching and connection settings.
+///
+/// The defaults match [`Client::new`]. For bulk jobs making many requests in a
+/// row, keeping one idle connection per host with TCP keep-alive avoids setting
+/// up a new connection for every request:
+///
+/// ```no_run
+/// # fn main() -> codeforces::Result<()> {
+/// let client = codeforces::Client::builder()
+///     .pool_max_idle_per_host(1)
+///     .tcp_keepalive(Some(std::time::Duration::from_secs(60)))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// On wasm32, the browser manages connections and compression: the connection
+/// settings, the compression toggle and the timeout are ignored, and standings
+/// are only cached in memory.
+#[derive(Debug)]
+pub struct ClientBuilder {
+    base_url: String,
+    http2_prior_knowledge: bool,
+    compression: bool,
+    pool_max_idle_per_host: Option<usize>,
+    tcp_keepalive: Option<Duration>,
+    timeout: Option<Duration>,
+    call_limit_retries: usize,
+    lang: Option<String>,
+    rate_limit: (usize, Duration),
+    rate_limit_mode: RateLimitMode,
+    concurrency_limit: Option<usize>,
+    cache_finished_standings: bool,
+    standings_cache_dir: Option<PathBuf>,
+    on_request: Option<RequestHook>,
+    rate_limit_registry: Option<RateLimitRegistry>,
+    #[cfg(feature = "middleware")]
+    middleware: Option<reqwest_middleware::ClientWithMiddleware>,
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self {
+            base_url: API_URL.to_owned(),
+            http2_prior_knowledge: false,
+            compression: true,
+            pool_max_idle_per_host: None,
+            tcp_keepalive: None,
+            timeout: None,
+            call_limit_retries: CALL_LIMIT_RETRIES,
+            lang: None,
+            rate_limit: RATE_LIMIT,
+            rate_limit_mode: RateLimitMode::Burst,
+            concurrency_limit: None,
+            cache_finished_standings: false,
+            standings_cache_dir: None,
+            on_request: None,
+            rate_limit_registry: None,
+            #[cfg(feature = "middleware")]
+            middleware: None,
+        }
     }
 }
 
-mod rate_limit {
-    /// Provides a simple ratelimit lock (that only works in tokio)
-    // use tokio::time::
+impl ClientBuilder {
+    /// Sets the base URL of API methods. See [`Client::with_base_url`].
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = url.into().trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Only use HTTP/2, without negotiating it first.
+    ///
+    /// HTTPS connections already use HTTP/2 when the server supports it; this
+    /// is only useful for plain HTTP proxies known to speak HTTP/2.
+    pub fn http2_prior_knowledge(mut self, value: bool) -> Self {
+        self.http2_prior_knowledge = value;
+        self
+    }
+
+    /// Asks for gzip-compressed responses, and decompresses them. Enabled by
+    /// default: large standings compress very well.
+    pub fn compression(mut self, enabled: bool) -> Self {
+        self.compression = enabled;
+        self
+    }
+
+    /// Sets the maximum number of idle connections kept per host.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Sets the TCP keep-alive interval of connections, or disables it with `None`.
+    pub fn tcp_keepalive(mut self, interval: Option<Duration>) -> Self {
+        self.tcp_keepalive = interval;
+        self
+    }
+
+    /// Fails requests that take longer than `timeout` to complete, from
+    /// connecting until the response is read. By default, requests never time out.
+    ///
+    /// Time spent waiting for the rate limit does not count.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how many times a request is retried when Codeforces reports that
+    /// the call limit is exceeded. Defaults to 3; 0 disables retrying.
+    pub fn call_limit_retries(mut self, retries: usize) -> Self {
+        self.call_limit_retries = retries;
+        self
+    }
+
+    /// Requests the responses in the given language, `en` or `ru`, e.g. for
+    /// contest and problem names. By default, Codeforces answers in English.
+    pub fn lang(mut self, lang: impl Into<String>) -> Self {
+        self.lang = Some(lang.into());
+        self
+    }
+
+    /// Allows `count` requests every `per`, counted from the moment each
+    /// request completes.
+    ///
+    /// Defaults to 1 request every two seconds, as required by Codeforces.
+    /// Raise it only for mirrors or proxies that allow more.
+    /// [`build`] fails if `count` is 0, which would never allow a request.
+    ///
+    /// [`build`]: ClientBuilder::build
+    pub fn rate_limit(mut self, count: usize, per: Duration) -> Self {
+        self.rate_limit = (count, per);
+        self
+    }
+
+    /// Sets how the rate limit hands out its first requests.
+    /// Defaults to [`RateLimitMode::Burst`].
+    pub fn rate_limit_mode(mut self, mode: RateLimitMode) -> Self {
+        self.rate_limit_mode = mode;
+        self
+    }
+
+    /// Allows at most `limit` requests in flight at once, independently of
+    /// the rate limit. By default, only the rate limit applies.
+    ///
+    /// [`build`] fails if `limit` is 0, which would never allow a request.
+    ///
+    /// [`build`]: ClientBuilder::build
+    pub fn concurrency_limit(mut self, limit: usize) -> Self {
+        self.concurrency_limit = Some(limit);
+        self
+    }
+
+    /// Caches the standings of finished contests, which never change, for
+    /// the lifetime of the Client. The cache is shared with its clones.
+    ///
+    /// Standings of contests in any other phase are always fetched.
+    pub fn cache_finished_standings(mut self, enabled: bool) -> Self {
+        self.cache_finished_standings = enabled;
+        self
+    }
+
+    /// Also keeps the cached standings of finished contests in `dir`, so that
+    /// they survive across Clients. Implies [`cache_finished_standings`].
+    ///
+    /// [`cache_finished_standings`]: ClientBuilder::cache_finished_standings
+    pub fn standings_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.standings_cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Calls `hook` with the method name and query parameters before each
+    /// API call, e.g. for audit logging. Secret parameters are redacted.
+    ///
+    /// The hook runs once per call, before waiting for the rate limit, and
+    /// not again when the call is retried. Calls answered from a cache are
+    /// not reported.
+    pub fn on_request(
+        mut self,
+        hook: impl Fn(&str, &[(String, String)]) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_request = Some(RequestHook(Arc::new(hook)));
+        self
+    }
+
+    /// Shares the rate limit with the other Clients built with `registry`
+    /// and the same base URL host. See [`RateLimitRegistry`].
+    pub fn rate_limit_registry(mut self, registry: &RateLimitRegistry) -> Self {
+        self.rate_limit_registry = Some(registry.clone());
+        self
+    }
+
+    /// Sends the API calls through `client` and its middleware, e.g. for
+    /// tracing, under the rate and concurrency limits of the Client.
+    ///
+    /// The connection settings, compression toggle and timeout of this
+    /// builder do not apply to `client`, which comes with its own. Other
+    /// requests, such as [`Client::server_time`] and those of
+    /// [`Client::try_borrow_timeout`], skip the middleware.
+    ///
+    /// Only available with the `middleware` feature.
+    #[cfg(feature = "middleware")]
+    pub fn middleware(mut self, client: reqwest_middleware::ClientWithMiddleware) -> Self {
+        self.middleware = Some(client);
+        self
+    }
+
+    /// The builder of the HTTP client, with the connection settings and the
+    /// timeout applied. The browser handles those on wasm32.
+    fn http_builder(&self) -> reqwest::ClientBuilder {
+        let http = HTTP::builder();
+        #[cfg(not(target_arch = "wasm32"))]
+        let http = {
+            let mut http = http
+                .tcp_keepalive(self.tcp_keepalive)
+                .gzip(self.compression);
+            if self.http2_prior_knowledge {
+                http = http.http2_prior_knowledge();
+            }
+            if let Some(max) = self.pool_max_idle_per_host {
+                http = http.pool_max_idle_per_host(max);
+            }
+            if let Some(timeout) = self.timeout {
+                http = http.timeout(timeout);
+            }
+            http
+        };
+        http
+    }
+
+    /// Builds the Client.
+    pub fn build(self) -> Result<Client> {
+        if self.rate_limit.0 == 0 {
+            return Err(Error::Local(
+                "rate limit must allow at least one request".to_owned(),
+            ));
+        }
+        if self.concurrency_limit == Some(0) {
+            return Err(Error::Local(
+                "concurrency limit must allow at least one request".to_owned(),
+            ));
+        }
+        let http = self.http_builder().build()?;
+        #[cfg(all(feature = "scrape", not(target_arch = "wasm32")))]
+        let no_redirect = self
+            .http_builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        let host = reqwest::Url::parse(&self.base_url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_owned))
+            .unwrap_or_else(|| self.base_url.clone());
+        let ((count, per), mode) = (self.rate_limit, self.rate_limit_mode);
+        let limit = match self.rate_limit_registry {
+            Some(r) => r.limit(host, (count, per), mode),
+            None => Arc::new(rate_limit::Ratelimit::new((), count, per, mode)),
+        };
+        let standings_cache = if self.cache_finished_standings || self.standings_cache_dir.is_some()
+        {
+            Some(Arc::new(StandingsCache::new(self.standings_cache_dir)))
+        } else {
+            None
+        };
+        Ok(Client {
+            http,
+            #[cfg(all(feature = "scrape", not(target_arch = "wasm32")))]
+            no_redirect,
+            limit,
+            base_url: self.base_url,
+            time_offset: Arc::new(AtomicI64::new(0)),
+            waited: Arc::new(AtomicU64::new(0)),
+            concurrency: self.concurrency_limit.map(|n| Arc::new(Semaphore::new(n))),
+            standings_cache,
+            call_limit_retries: self.call_limit_retries,
+            lang: self.lang,
+            counters: Arc::default(),
+            on_request: self.on_request,
+            #[cfg(feature = "middleware")]
+            middleware: self.middleware,
+            #[cfg(any(test, feature = "test-util"))]
+            mock: None,
+        })
+    }
+}
+
+/// Shares rate limits between Clients, keyed by the host of their base URL,
+/// e.g. so that several Clients calling `codeforces.com` in a process stay
+/// within one budget.
+///
+/// The rate limit of a host is set by the first Client built for it; the
+/// settings of later Clients are ignored. Clones share the same registry.
+///
+/// ```no_run
+/// # fn main() -> codeforces::Result<()> {
+/// let registry = codeforces::RateLimitRegistry::new();
+/// let a = codeforces::Client::builder().rate_limit_registry(&registry).build()?;
+/// let b = codeforces::Client::builder().rate_limit_registry(&registry).build()?;
+/// // `a` and `b` send at most one request every two seconds between them.
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct RateLimitRegistry {
+    limits: Arc<Mutex<HashMap<String, Arc<rate_limit::Ratelimit<()>>>>>,
+}
+
+impl std::fmt::Debug for RateLimitRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let limits = self.limits.lock().unwrap();
+        f.debug_struct("RateLimitRegistry")
+            .field("hosts", &limits.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl RateLimitRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The rate limit of `host`, created if there is none yet or if the
+    /// previous one was shut down.
+    fn limit(
+        &self,
+        host: String,
+        (count, per): (usize, Duration),
+        mode: RateLimitMode,
+    ) -> Arc<rate_limit::Ratelimit<()>> {
+        let mut limits = self.limits.lock().unwrap();
+        let limit = limits
+            .entry(host)
+            .or_insert_with(|| Arc::new(rate_limit::Ratelimit::new((), count, per, mode)));
+        if limit.is_closed() {
+            *limit = Arc::new(rate_limit::Ratelimit::new((), count, per, mode));
+        }
+        limit.clone()
+    }
+}
+
+/// How a rate limit of `count` requests every `per` starts out.
+///
+/// Either way, each request frees its slot `per` after it completes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitMode {
+    /// All `count` requests can be sent at once.
+    Burst,
+    /// Slots become available one at a time, evenly spread over the first
+    /// `per`: the first request is sent at once, the next one `per / count`
+    /// later, and so on.
+    Smooth,
+}
+
+pub(crate) mod rate_limit {
+    /// Provides a simple ratelimit lock, running its timers on tokio or, on
+    /// `wasm32`, in the browser.
     use std::time::Duration;
 
-    use flume::{bounded as channel, Receiver, Sender};
+    use super::RateLimitMode;
     use std::ops::Deref;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::sync::{Notify, Semaphore};
 
     /// Holds the underlying `T` in a rate-limited way.
+    ///
+    /// Permits are handed out fairly: concurrent `borrow`s are served in the
+    /// order they started waiting.
     pub struct Ratelimit<T> {
         inner: T,
-        recv: Receiver<()>,
-        send: Sender<()>,
+        permits: Arc<Semaphore>,
+        active: Active,
+        /// The permits still to hand out, in smooth mode.
+        ramp: Option<Ramp>,
 
         wait_time: Duration,
     }
 
+    /// Hands out permits one at a time, starting at the first `borrow`.
+    struct Ramp {
+        started: AtomicBool,
+        count: usize,
+    }
+
+    /// Tracks the number of references currently held.
+    #[derive(Default)]
+    struct Active {
+        count: AtomicUsize,
+        released: Notify,
+    }
+
     struct RatelimitGuard<'a, T> {
         inner: &'a T,
-        send: &'a Sender<()>,
+        permits: &'a Arc<Semaphore>,
+        active: &'a Active,
         wait_time: &'a Duration,
     }
 
     impl<T> Ratelimit<T> {
         /// Create a new ratelimit with at most `count` uses in `wait_time`.
-        pub fn new(inner: T, count: usize, wait_time: Duration) -> Self {
-            let (send, recv) = channel(count);
-            (0..count).for_each(|_| {
-                send.send(()).ok();
-            });
+        ///
+        /// In smooth mode, the `count` uses are spread over the first
+        /// `wait_time`, starting at the first `borrow`.
+        pub fn new(inner: T, count: usize, wait_time: Duration, mode: RateLimitMode) -> Self {
+            let (permits, ramp) = match mode {
+                RateLimitMode::Burst => (count, None),
+                RateLimitMode::Smooth => (
+                    0,
+                    Some(Ramp {
+                        started: AtomicBool::new(false),
+                        count,
+                    }),
+                ),
+            };
             Self {
                 inner,
-                send,
-                recv,
+                permits: Arc::new(Semaphore::new(permits)),
+                active: Active::default(),
+                ramp,
                 wait_time,
             }
         }
 
+        /// Starts handing out the permits of smooth mode, if not yet started.
+        fn start_ramp(&self) {
+            let ramp = match self.ramp {
+                Some(ref ramp) if !ramp.started.swap(true, Ordering::SeqCst) => ramp,
+                _ => return,
+            };
+            let (permits, count) = (self.permits.clone(), ramp.count);
+            let step = self.wait_time / count.max(1) as u32;
+            crate::rt::spawn(async move {
+                for i in 0..count {
+                    if i > 0 {
+                        crate::rt::sleep(step).await;
+                    }
+                    permits.add_permits(1);
+                }
+            });
+        }
+
+        /// Whether the ratelimit was closed.
+        pub fn is_closed(&self) -> bool {
+            self.permits.is_closed()
+        }
+
         /// Borrow the inner `T`. You can only hol this reference `count` times in `wait_time`.
         /// The clock counts from the moment the ref is dropped.
-        pub async fn borrow<'a>(&'a self) -> impl Deref<Target = T> + 'a {
-            self.recv.recv_async().await.unwrap();
-            RatelimitGuard {
+        ///
+        /// Returns `None` once the ratelimit is closed.
+        pub async fn borrow<'a>(&'a self) -> Option<impl Deref<Target = T> + 'a> {
+            self.start_ramp();
+            self.permits.acquire().await.ok()?.forget();
+            self.active.count.fetch_add(1, Ordering::SeqCst);
+            Some(RatelimitGuard {
                 inner: &self.inner,
-                send: &self.send,
+                permits: &self.permits,
+                active: &self.active,
                 wait_time: &self.wait_time,
+            })
+        }
+
+        /// Closes the ratelimit, failing all current and future `borrow`s,
+        /// then waits for all held references to be dropped.
+        pub async fn close(&self) {
+            self.permits.close();
+            loop {
+                // Created before checking, so that no release is missed.
+                let released = self.active.released.notified();
+                if self.active.count.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                released.await;
             }
         }
     }
@@ -79,11 +1012,14 @@ mod rate_limit {
 
     impl<'a, T> Drop for RatelimitGuard<'a, T> {
         fn drop(&mut self) {
-            let send = self.send.clone();
-            let wait_time = self.wait_time.clone();
-            tokio::spawn(async move {
-                tokio::time::sleep(wait_time).await;
-                send.send_async(()).await.ok();
+            self.active.count.fetch_sub(1, Ordering::SeqCst);
+            self.active.released.notify_waiters();
+
+            let permits = self.permits.clone();
+            let wait_time = *self.wait_time;
+            crate::rt::spawn(async move {
+                crate::rt::sleep(wait_time).await;
+                permits.add_permits(1);
             });
         }
     }
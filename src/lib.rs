@@ -1,13 +1,28 @@
+use futures_util::stream::{self, Stream, StreamExt};
 use reqwest::Error as HttpError;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Arc;
 use std::{borrow::Borrow, fmt};
 
 pub mod client;
-pub use client::Client;
+pub use client::{Client, ClientBuilder, Metrics, RateLimitMode, RateLimitRegistry};
+
+pub mod borrowed;
+pub mod compact;
+mod rt;
+mod standings;
+pub use standings::{SolveTimes, Standings, StandingsIter};
+
+pub use tokio_util::sync::CancellationToken;
 
 #[cfg(test)]
 mod test;
 
+/// The response envelope of all API methods.
+///
+/// A present `result`, even an empty array, is a success; only a missing
+/// `result` is an error.
 #[derive(Debug, Deserialize)]
 #[serde(bound(deserialize = "T: for<'t> Deserialize<'t>"))]
 struct CFResult<T: for<'t> Deserialize<'t>> {
@@ -19,22 +34,67 @@ impl<T: for<'t> Deserialize<'t>> From<CFResult<T>> for Result<T> {
     fn from(c: CFResult<T>) -> Self {
         match c.result {
             Some(v) => Ok(v),
-            None => Err(Error::Codeforces(
-                c.comment.unwrap_or("Unknown error".to_owned()),
-            )),
+            None => Err(envelope_error(c.comment)),
         }
     }
 }
 
+/// The error of a response envelope without a `result`, from its `comment`.
+fn envelope_error(comment: Option<String>) -> Error {
+    Error::from_comment(comment.unwrap_or_else(|| "Unknown error".to_owned()))
+}
+
+/// Decodes a raw API response, as sent back by codeforces.
+///
+/// Not part of the public API: exposed for the fuzz targets.
+#[doc(hidden)]
+pub fn decode_response<T: for<'t> Deserialize<'t>>(bytes: &[u8]) -> Result<T> {
+    serde_json::from_slice::<CFResult<T>>(bytes)?.into()
+}
+
+/// Like [`decode_response`], for the very large responses of bulk methods.
+///
+/// With the `simd-json` feature, the response is parsed with `simd-json`,
+/// from a copy of `bytes` since it parses in place. Otherwise, this is
+/// [`decode_response`].
+///
+/// Not part of the public API: exposed for the benchmarks.
+#[doc(hidden)]
+pub fn decode_bulk_response<T: for<'t> Deserialize<'t>>(bytes: &[u8]) -> Result<T> {
+    #[cfg(feature = "simd-json")]
+    {
+        let mut bytes = bytes.to_vec();
+        simd_json::serde::from_slice::<CFResult<T>>(&mut bytes)
+            .map_err(|e| Error::Decode(serde::de::Error::custom(e)))?
+            .into()
+    }
+    #[cfg(not(feature = "simd-json"))]
+    {
+        decode_response(bytes)
+    }
+}
+
 /// The error returned.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// Occurred from within reqwest.
     Http(HttpError),
     /// Decoding error,
     Decode(serde_json::Error),
     /// Sent back from codeforces.
-    Codeforces(String),
+    Codeforces(ApiError),
+    /// Sent back from codeforces when access is denied, e.g. to a private contest.
+    Forbidden(ApiError),
+    /// The client was shut down with [`Client::shutdown`].
+    Shutdown,
+    /// No rate-limit slot freed up in time, see [`Client::try_borrow_timeout`].
+    RateLimitTimeout,
+    /// Occurred from within a reqwest middleware, with the `middleware` feature.
+    Middleware(Box<dyn std::error::Error + Send + Sync>),
+    /// Raised by the client itself rather than sent back from codeforces,
+    /// e.g. for invalid [`ClientBuilder`] settings or a missing `Date` header.
+    Local(String),
 }
 
 impl fmt::Display for Error {
@@ -42,7 +102,125 @@ impl fmt::Display for Error {
         match self {
             Error::Http(ref e) => write!(f, "HTTP: {}", e),
             Error::Decode(ref e) => write!(f, "Decode: {}", e),
-            Error::Codeforces(ref s) => write!(f, "Codeforces: {}", s),
+            Error::Codeforces(ref e) => write!(f, "Codeforces: {}", e),
+            Error::Forbidden(ref e) => write!(f, "Forbidden: {}", e),
+            Error::Shutdown => write!(f, "The client has been shut down"),
+            Error::Local(ref e) => write!(f, "Local: {}", e),
+            Error::RateLimitTimeout => write!(f, "Timed out waiting for the rate limit"),
+            Error::Middleware(ref e) => write!(f, "Middleware: {}", e),
+        }
+    }
+}
+
+/// Comments of access denied errors, after the `field: ` prefix.
+const FORBIDDEN_COMMENTS: &[&str] = &["You are not allowed", "You have no access"];
+
+impl Error {
+    /// Classifies an error comment sent back from codeforces.
+    fn from_comment(comment: String) -> Self {
+        // Comments are usually prefixed with the offending parameter, e.g.
+        // `contestId: You are not allowed to view the contest`.
+        let message = comment.split_once(": ").map_or(&comment[..], |(_, m)| m);
+        if FORBIDDEN_COMMENTS.iter().any(|p| message.starts_with(p)) {
+            Error::Forbidden(comment.into())
+        } else {
+            Error::Codeforces(comment.into())
+        }
+    }
+
+    /// Whether Codeforces rejected the call for exceeding the call limit.
+    pub fn is_call_limit_exceeded(&self) -> bool {
+        matches!(self, Error::Codeforces(ref e) if e.comment.starts_with("Call limit exceeded"))
+    }
+
+    /// The underlying reqwest error, if this is an [`Error::Http`].
+    pub fn as_http(&self) -> Option<&HttpError> {
+        match self {
+            Error::Http(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Consumes the error, returning the underlying reqwest error if this is
+    /// an [`Error::Http`].
+    pub fn into_http(self) -> Option<HttpError> {
+        match self {
+            Error::Http(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Attaches the failing call to errors sent back from codeforces.
+    pub(crate) fn with_call(self, method: &str, params: &[(&str, String)]) -> Self {
+        match self {
+            Error::Codeforces(e) => Error::Codeforces(e.with_call(method, params)),
+            Error::Forbidden(e) => Error::Forbidden(e.with_call(method, params)),
+            e => e,
+        }
+    }
+}
+
+/// An error sent back from codeforces, along with the API call that failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiError {
+    /// The comment returned by codeforces.
+    pub comment: String,
+    /// The API method that was called, e.g. `user.info`.
+    pub method: Option<String>,
+    /// The query parameters of the call. Secret parameters are redacted.
+    pub params: Vec<(String, String)>,
+}
+
+/// Query parameters whose values are never kept in an [`ApiError`].
+const SECRET_PARAMS: &[&str] = &["apiKey", "apiSig"];
+
+/// Copies the query parameters, replacing the values of [`SECRET_PARAMS`].
+pub(crate) fn redact_params(params: &[(&str, String)]) -> Vec<(String, String)> {
+    params
+        .iter()
+        .map(|(k, v)| {
+            let v = if SECRET_PARAMS.contains(k) {
+                "<redacted>".to_owned()
+            } else {
+                v.clone()
+            };
+            (k.to_string(), v)
+        })
+        .collect()
+}
+
+impl ApiError {
+    /// Attaches the failing call to the error, unless one is already attached.
+    pub(crate) fn with_call(mut self, method: &str, params: &[(&str, String)]) -> Self {
+        if self.method.is_none() {
+            self.method = Some(method.to_owned());
+            self.params = redact_params(params);
+        }
+        self
+    }
+}
+
+impl From<String> for ApiError {
+    fn from(comment: String) -> Self {
+        Self {
+            comment,
+            method: None,
+            params: Vec::new(),
+        }
+    }
+}
+
+impl From<&str> for ApiError {
+    fn from(comment: &str) -> Self {
+        comment.to_owned().into()
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.method {
+            Some(ref method) => write!(f, "{} (in {})", self.comment, method),
+            None => write!(f, "{}", self.comment),
         }
     }
 }
@@ -52,7 +230,12 @@ impl std::error::Error for Error {
         match self {
             Error::Http(ref e) => Some(e),
             Error::Decode(ref e) => Some(e),
-            Error::Codeforces(_) => None,
+            Error::Middleware(ref e) => Some(&**e),
+            Error::Codeforces(_)
+            | Error::Forbidden(_)
+            | Error::Shutdown
+            | Error::RateLimitTimeout
+            | Error::Local(_) => None,
         }
     }
 }
@@ -63,17 +246,120 @@ impl From<HttpError> for Error {
     }
 }
 
+#[cfg(feature = "middleware")]
+impl From<reqwest_middleware::Error> for Error {
+    fn from(e: reqwest_middleware::Error) -> Self {
+        match e {
+            reqwest_middleware::Error::Reqwest(e) => Error::Http(e),
+            reqwest_middleware::Error::Middleware(e) => Error::Middleware(e.into()),
+        }
+    }
+}
+
 impl From<serde_json::Error> for Error {
     fn from(e: serde_json::Error) -> Self {
         Error::Decode(e)
     }
 }
 
+/// Maps errors to the closest [`std::io::ErrorKind`], keeping the error itself
+/// as the inner error.
+///
+/// - Timed out requests map to `TimedOut`, failed connections to `ConnectionRefused`.
+/// - Decoding errors map to `InvalidData`.
+/// - Denied access maps to `PermissionDenied`.
+/// - A shut down client maps to `NotConnected`.
+/// - Everything else maps to `Other`.
+impl From<Error> for std::io::Error {
+    fn from(e: Error) -> Self {
+        use std::io::ErrorKind;
+        let kind = match e {
+            Error::Http(ref e) if e.is_timeout() => ErrorKind::TimedOut,
+            #[cfg(not(target_arch = "wasm32"))]
+            Error::Http(ref e) if e.is_connect() => ErrorKind::ConnectionRefused,
+            Error::Decode(_) => ErrorKind::InvalidData,
+            Error::Forbidden(_) => ErrorKind::PermissionDenied,
+            Error::Shutdown => ErrorKind::NotConnected,
+            Error::RateLimitTimeout => ErrorKind::TimedOut,
+            Error::Http(_) | Error::Codeforces(_) | Error::Middleware(_) | Error::Local(_) => {
+                ErrorKind::Other
+            }
+        };
+        std::io::Error::new(kind, e)
+    }
+}
+
 /// The result type.
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// The id of a contest.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(transparent)]
+pub struct ContestId(pub u64);
+
+impl From<u64> for ContestId {
+    fn from(id: u64) -> Self {
+        ContestId(id)
+    }
+}
+
+impl From<ContestId> for u64 {
+    fn from(id: ContestId) -> Self {
+        id.0
+    }
+}
+
+impl fmt::Display for ContestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The index of a problem within a contest, e.g. `A` or `B1`.
+///
+/// Indices are ordered as Codeforces displays them, see [`problem_index_cmp`].
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct ProblemIndex(pub String);
+
+impl From<&str> for ProblemIndex {
+    fn from(index: &str) -> Self {
+        ProblemIndex(index.to_owned())
+    }
+}
+
+impl From<String> for ProblemIndex {
+    fn from(index: String) -> Self {
+        ProblemIndex(index)
+    }
+}
+
+impl AsRef<str> for ProblemIndex {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ProblemIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl PartialOrd for ProblemIndex {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ProblemIndex {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        problem_index_cmp(&self.0, &other.0)
+    }
+}
+
 /// A codeforces user.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct User {
     pub handle: String,
@@ -88,19 +374,86 @@ pub struct User {
     pub max_rank: Option<String>,
     pub rating: Option<i64>,
     pub max_rating: Option<i64>,
-    pub last_online_time_seconds: u64,
+    /// `None` for some special accounts, e.g. deleted or system ones, that
+    /// leave it out.
+    pub last_online_time_seconds: Option<u64>,
     pub registration_time_seconds: u64,
     pub friend_of_count: u64,
     pub avatar: String,
     pub title_photo: String,
 }
 
+/// Prints the handle, followed by the rank and rating, e.g.
+/// `tourist (legendary grandmaster, 3800)`, or `(unrated)`.
+impl fmt::Display for User {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.rank, self.rating) {
+            (Some(rank), Some(rating)) => write!(f, "{} ({}, {})", self.handle, rank, rating),
+            (None, Some(rating)) => write!(f, "{} ({})", self.handle, rating),
+            (Some(rank), None) => write!(f, "{} ({})", self.handle, rank),
+            (None, None) => write!(f, "{} (unrated)", self.handle),
+        }
+    }
+}
+
+/// Turns a protocol-relative URL (`//userpic.codeforces.org/...`) into an
+/// `https://` one. Other URLs are returned as is.
+fn absolute_url(url: &str) -> String {
+    match url.strip_prefix("//") {
+        Some(rest) => format!("https://{}", rest),
+        None => url.to_owned(),
+    }
+}
+
+/// Checks that `handle` is a well-formed Codeforces handle.
+///
+/// A handle consists of 3 to 24 characters, each being a latin letter, a digit,
+/// or one of `_`, `-` and `.`.
+pub fn validate_handle(handle: &str) -> Result<()> {
+    let valid_char = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.';
+    if (3..=24).contains(&handle.len()) && handle.chars().all(valid_char) {
+        Ok(())
+    } else {
+        Err(Error::Codeforces(
+            format!("invalid handle: {:?}", handle).into(),
+        ))
+    }
+}
+
 impl User {
     /// URL to the profile of the user.
     pub fn profile_url(&self) -> String {
         format!("https://codeforces.com/profile/{}", self.handle)
     }
 
+    /// The absolute URL of the avatar of the user.
+    pub fn avatar_url(&self) -> String {
+        absolute_url(&self.avatar)
+    }
+
+    /// The absolute URL of the title photo of the user.
+    pub fn title_photo_url(&self) -> String {
+        absolute_url(&self.title_photo)
+    }
+
+    /// How long ago the user registered, in seconds, as of `now_seconds`.
+    /// Returns 0 if `now_seconds` is before the registration.
+    pub fn account_age_seconds(&self, now_seconds: u64) -> u64 {
+        now_seconds.saturating_sub(self.registration_time_seconds)
+    }
+
+    /// Whether the user registered strictly before `time_seconds`.
+    pub fn registered_before(&self, time_seconds: u64) -> bool {
+        self.registration_time_seconds < time_seconds
+    }
+
+    /// The user as a JSON value, with the same keys as the API object,
+    /// e.g. to feed into a template engine.
+    pub fn to_value(&self) -> serde_json::Value {
+        // Plain fields with string keys always serialize.
+        serde_json::to_value(self).unwrap()
+    }
+
     /// The color of their username.
     pub fn color(&self) -> u64 {
         match self.rating {
@@ -128,8 +481,47 @@ impl User {
     }
 }
 
+/// The short code of a rank, as found in [`User::rank`], e.g. `IGM` for
+/// `international grandmaster`. Returns `None` for unknown ranks.
+pub fn rank_short_code(rank: &str) -> Option<&'static str> {
+    Some(match rank {
+        "newbie" => "N",
+        "pupil" => "P",
+        "specialist" => "S",
+        "expert" => "E",
+        "candidate master" => "CM",
+        "master" => "M",
+        "international master" => "IM",
+        "grandmaster" => "GM",
+        "international grandmaster" => "IGM",
+        "legendary grandmaster" => "LGM",
+        _ => return None,
+    })
+}
+
+/// The percentage (from 0 to 100) of `users` whose rating is strictly lower
+/// than `rating`.
+///
+/// `users` must be sorted by decreasing rating, as returned by [`User::rated_list`].
+/// A rating above the top gives 100, and one below the bottom gives 0.
+pub fn rated_list_percentile(users: &[User], rating: i64) -> f64 {
+    if users.is_empty() {
+        return 0.0;
+    }
+    let at_least = users.partition_point(|u| u.rating.unwrap_or(0) >= rating);
+    (users.len() - at_least) as f64 * 100.0 / users.len() as f64
+}
+
+/// The rank `rating` would have among `users`, i.e. one more than the number of
+/// users with a strictly higher rating.
+///
+/// `users` must be sorted by decreasing rating, as returned by [`User::rated_list`].
+pub fn rated_list_rank(users: &[User], rating: i64) -> usize {
+    users.partition_point(|u| u.rating.unwrap_or(0) > rating) + 1
+}
+
 /// An user's rating change.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct RatingChange {
     pub contest_id: u64,
@@ -141,8 +533,77 @@ pub struct RatingChange {
     pub new_rating: i64,
 }
 
+impl RatingChange {
+    /// The rating gained, negative if rating was lost.
+    pub fn delta(&self) -> i64 {
+        self.new_rating - self.old_rating
+    }
+
+    /// Whether rating was gained.
+    pub fn is_positive(&self) -> bool {
+        self.delta() > 0
+    }
+
+    /// The change as a point of a rating graph: the update time, in seconds
+    /// since the Unix epoch, and the new rating.
+    pub fn as_point(&self) -> (u64, i64) {
+        (self.rating_update_time_seconds, self.new_rating)
+    }
+
+    /// The change as a JSON value, with the same keys as the API object.
+    /// See [`User::to_value`].
+    pub fn to_value(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap()
+    }
+}
+
+/// The points of a rating graph, see [`RatingChange::as_point`], ordered by
+/// time.
+pub fn rating_series(changes: &[RatingChange]) -> Vec<(u64, i64)> {
+    let mut points: Vec<_> = changes.iter().map(RatingChange::as_point).collect();
+    points.sort_by_key(|&(time, _)| time);
+    points
+}
+
+/// The current streak of rating changes: `n` if the last `n` changes were all
+/// gains, `-n` if they were all losses.
+///
+/// Returns 0 if there are no changes or the last one left the rating unchanged.
+/// `changes` must be sorted by time, as returned by [`User::rating`].
+pub fn rating_streak(changes: &[RatingChange]) -> i64 {
+    let sign = match changes.last() {
+        Some(last) => last.delta().signum(),
+        None => return 0,
+    };
+    if sign == 0 {
+        return 0;
+    }
+    let n = changes
+        .iter()
+        .rev()
+        .take_while(|c| c.delta().signum() == sign)
+        .count();
+    sign * n as i64
+}
+
+/// Returns the rating changes that happened between `from_seconds` and
+/// `to_seconds`, both inclusive.
+///
+/// `changes` must be sorted by `rating_update_time_seconds`, which is the case
+/// for the results of [`User::rating`].
+pub fn rating_in_range(
+    changes: &[RatingChange],
+    from_seconds: u64,
+    to_seconds: u64,
+) -> &[RatingChange] {
+    let start = changes.partition_point(|c| c.rating_update_time_seconds < from_seconds);
+    let end = changes.partition_point(|c| c.rating_update_time_seconds <= to_seconds);
+    &changes[start..end.max(start)]
+}
+
 /// The scoring type of a contest.
 #[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum ContestType {
     CF,
     IOI,
@@ -162,6 +623,7 @@ impl fmt::Display for ContestType {
 /// The current phase of the contest.
 #[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[non_exhaustive]
 pub enum ContestPhase {
     Before,
     Coding,
@@ -170,6 +632,25 @@ pub enum ContestPhase {
     Finished,
 }
 
+impl ContestPhase {
+    /// A sensible interval between polls of the standings of a contest in
+    /// this phase, e.g. for [`Contest::watch_standings`].
+    ///
+    /// Results change the fastest during system tests, and not at all once
+    /// the contest is finished.
+    pub fn recommended_poll_interval(&self) -> std::time::Duration {
+        use ContestPhase::*;
+        let seconds = match self {
+            Before => 300,
+            Coding => 30,
+            PendingSystemTest => 60,
+            SystemTest => 10,
+            Finished => 3600,
+        };
+        std::time::Duration::from_secs(seconds)
+    }
+}
+
 impl fmt::Display for ContestPhase {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use ContestPhase::*;
@@ -187,6 +668,34 @@ impl fmt::Display for ContestPhase {
     }
 }
 
+/// The category of a contest, parsed from its `kind`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContestKind {
+    /// An official contest: a Codeforces round or an official competition.
+    Official,
+    Gym,
+    Mashup,
+    Training,
+    /// Any other kind, as given by Codeforces.
+    Other(String),
+}
+
+/// Parses the category from the first word of the kind, compared
+/// case-insensitively, e.g. `Official ICPC Contest` or `Training Camp Contest`.
+/// Kinds such as `Unofficial Contest` are [`ContestKind::Other`].
+impl From<&str> for ContestKind {
+    fn from(kind: &str) -> Self {
+        let first = kind.split_whitespace().next().unwrap_or("").to_lowercase();
+        match first.as_str() {
+            "official" => ContestKind::Official,
+            "training" => ContestKind::Training,
+            "mashup" => ContestKind::Mashup,
+            "gym" => ContestKind::Gym,
+            _ => ContestKind::Other(kind.to_owned()),
+        }
+    }
+}
+
 /// A single contest.
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -203,7 +712,10 @@ pub struct Contest {
     pub prepared_by: Option<String>,
     pub website_url: Option<String>,
     pub description: Option<String>,
+    /// Difficulty from 1 to 5. Only present for gym contests and mashups.
     pub difficulty: Option<u8>,
+    /// A human-readable category, e.g. "Official ICPC Contest". Only present
+    /// for gym contests. See [`Contest::parsed_kind`].
     pub kind: Option<String>,
     pub icpc_region: Option<String>,
     pub country: Option<String>,
@@ -211,17 +723,103 @@ pub struct Contest {
     pub season: Option<String>,
 }
 
+/// Prints the name, followed by the phase, e.g. `Test Round (Finished)`.
+impl fmt::Display for Contest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.phase)
+    }
+}
+
+/// Contests are equal when they have the same id, whatever their phase or
+/// other fields, e.g. when listed at different times.
+impl PartialEq for Contest {
+    fn eq(&self, other: &Contest) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Contest {}
+
+impl std::hash::Hash for Contest {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
+
+/// Where and when an ICPC archive contest took place. See [`Contest::icpc_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IcpcInfo {
+    pub region: String,
+    pub country: String,
+    pub city: String,
+    /// The season, e.g. `2009-2010`.
+    pub season: String,
+}
+
 impl Contest {
     /// URL to the contest.
     pub fn url(&self) -> String {
         format!("https://codeforces.com/contests/{}", self.id)
     }
+
+    /// The ICPC metadata of the contest, if it has all of `icpc_region`,
+    /// `country`, `city` and `season`. This is only the case for some
+    /// contests of the gym's ICPC archive.
+    pub fn icpc_info(&self) -> Option<IcpcInfo> {
+        Some(IcpcInfo {
+            region: self.icpc_region.clone()?,
+            country: self.country.clone()?,
+            city: self.city.clone()?,
+            season: self.season.clone()?,
+        })
+    }
+
+    /// The category of the contest.
+    ///
+    /// Contests without a `kind` are regular Codeforces rounds ([`ContestKind::Official`])
+    /// if their id is below 100000, and plain gym contests otherwise.
+    pub fn parsed_kind(&self) -> ContestKind {
+        match self.kind {
+            Some(ref kind) => kind.as_str().into(),
+            None if self.id < 100000 => ContestKind::Official,
+            None => ContestKind::Gym,
+        }
+    }
+
+    /// The difficulty of the contest in stars, clamped to the 0 to 5 range.
+    ///
+    /// Returns `None` for contests without a difficulty (i.e. regular contests).
+    pub fn difficulty_stars(&self) -> Option<u8> {
+        self.difficulty.map(|d| d.min(5))
+    }
+
+    /// The difficulty of the contest displayed as a string of 5 stars,
+    /// e.g. `★★★☆☆`.
+    pub fn difficulty_star_string(&self) -> Option<String> {
+        self.difficulty_stars()
+            .map(|d| "★".repeat(d as usize) + &"☆".repeat(5 - d as usize))
+    }
+
+    /// Compares contests by start time, with unscheduled contests last.
+    pub fn cmp_by_start(&self, other: &Contest) -> std::cmp::Ordering {
+        match (self.start_time_seconds, other.start_time_seconds) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        }
+    }
+}
+
+/// Sorts contests by start time, with unscheduled contests last.
+/// See [`Contest::cmp_by_start`].
+pub fn sort_by_start(contests: &mut [Contest]) {
+    contests.sort_by(Contest::cmp_by_start)
 }
 
 /// The type of a problem.
-#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ProblemType {
+    #[default]
     Programming,
     Question,
 }
@@ -236,20 +834,183 @@ impl fmt::Display for ProblemType {
 }
 
 /// Represents a problem.
+///
+/// Problems embedded in submissions may be incomplete (e.g. for deleted
+/// problems): a missing `name` is left empty, a missing `type` defaults to
+/// [`ProblemType::Programming`] and missing `tags` to no tags.
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Problem {
     pub contest_id: Option<u64>,
     pub problemset_name: Option<String>,
     pub index: String,
+    #[serde(default)]
     pub name: String,
-    #[serde(rename = "type")]
+    #[serde(rename = "type", default)]
     pub problem_type: ProblemType,
     pub points: Option<f64>,
     pub rating: Option<u64>,
+    #[serde(default)]
     pub tags: Vec<String>,
 }
 
+impl Problem {
+    /// The contest id and index of the problem, which together identify it.
+    pub fn identity(&self) -> (Option<u64>, &str) {
+        (self.contest_id, &self.index)
+    }
+
+    /// The color of the problem's difficulty, or `None` if it is unrated.
+    /// See [`problem_rating_color`].
+    pub fn color(&self) -> Option<u64> {
+        self.rating.map(problem_rating_color)
+    }
+}
+
+/// The color of a problem difficulty, as shown on the website.
+///
+/// Unlike [`User::color`], difficulties from 2100 to 2399 are all orange:
+/// there is no separate color for master-level problems.
+pub fn problem_rating_color(rating: u64) -> u64 {
+    if rating < 1200 {
+        0x808080
+    } else if rating < 1400 {
+        0x008000
+    } else if rating < 1600 {
+        0x03a89e
+    } else if rating < 1900 {
+        0x0000ff
+    } else if rating < 2100 {
+        0xaa00aa
+    } else if rating < 2400 {
+        0xff8c00
+    } else {
+        0xff0000
+    }
+}
+
+/// Compares problem indices in the order Codeforces displays them.
+///
+/// The letters are compared first, then the number after them, if any:
+/// `A` < `A1` < `A2` < `A10` < `B`.
+pub fn problem_index_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    fn key(index: &str) -> (&str, Option<u64>, &str) {
+        let split = index
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(index.len());
+        let (letters, rest) = index.split_at(split);
+        (letters, rest.parse().ok(), rest)
+    }
+    key(a).cmp(&key(b))
+}
+
+/// The number of problems of each difficulty. See [`difficulty_histogram`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DifficultyHistogram {
+    /// The number of problems in each bucket, keyed by the lowest rating of
+    /// the bucket. All buckets are present, even empty ones.
+    pub buckets: std::collections::BTreeMap<u64, usize>,
+    /// The number of problems without a rating.
+    pub unrated: usize,
+}
+
+/// The lowest problem rating.
+const MIN_PROBLEM_RATING: u64 = 800;
+/// The highest problem rating.
+const MAX_PROBLEM_RATING: u64 = 3500;
+
+/// Buckets problems by rating, in steps of 100 from 800 to 3500.
+///
+/// A problem rated 1550 goes into the 1500 bucket. Ratings outside of the
+/// range go into the lowest or highest bucket.
+pub fn difficulty_histogram(problems: &[Problem]) -> DifficultyHistogram {
+    let mut histogram = DifficultyHistogram {
+        buckets: (MIN_PROBLEM_RATING..=MAX_PROBLEM_RATING)
+            .step_by(100)
+            .map(|r| (r, 0))
+            .collect(),
+        unrated: 0,
+    };
+    for problem in problems {
+        match problem.rating {
+            Some(rating) => {
+                let bucket = (rating / 100 * 100).clamp(MIN_PROBLEM_RATING, MAX_PROBLEM_RATING);
+                *histogram.buckets.entry(bucket).or_insert(0) += 1;
+            }
+            None => histogram.unrated += 1,
+        }
+    }
+    histogram
+}
+
+/// How far from the target rating [`recommend_problems`] looks.
+const RECOMMEND_RATING_WINDOW: u64 = 200;
+
+/// Recommends up to `n` problems from `pool` to solve next.
+///
+/// Problems already accepted in `solved` (compared by [`Problem::identity`])
+/// and unrated problems are left out. The rest are kept if their rating is
+/// within 200 of `target_rating`, closest first; ties keep the order of `pool`.
+pub fn recommend_problems<'a>(
+    solved: &[Submission],
+    pool: &'a [Problem],
+    target_rating: u64,
+    n: usize,
+) -> Vec<&'a Problem> {
+    let solved: std::collections::HashSet<_> = solved
+        .iter()
+        .filter(|s| s.verdict == Some(Verdict::Ok))
+        .map(|s| s.problem.identity())
+        .collect();
+    let mut candidates: Vec<(u64, &Problem)> = pool
+        .iter()
+        .filter(|p| !solved.contains(&p.identity()))
+        .filter_map(|p| Some((p.rating?.abs_diff(target_rating), p)))
+        .filter(|&(distance, _)| distance <= RECOMMEND_RATING_WINDOW)
+        .collect();
+    candidates.sort_by_key(|&(distance, _)| distance);
+    candidates.into_iter().take(n).map(|(_, p)| p).collect()
+}
+
+/// The number of users who solved a problem.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemStatistics {
+    pub contest_id: Option<u64>,
+    pub index: String,
+    pub solved_count: u64,
+}
+
+/// A problem along with the number of users who solved it.
+/// See [`Problemset::problems_with_stats`].
+#[derive(Debug, Clone)]
+pub struct ProblemWithStats {
+    pub problem: Problem,
+    pub solved_count: u64,
+}
+
+/// Joins problems with their statistics by contest id and index, keeping the
+/// order of `problems`. Problems without statistics are solved by no one.
+fn join_statistics(problems: Vec<Problem>, stats: &[ProblemStatistics]) -> Vec<ProblemWithStats> {
+    let solved: std::collections::HashMap<_, _> = stats
+        .iter()
+        .map(|s| ((s.contest_id, s.index.as_str()), s.solved_count))
+        .collect();
+    problems
+        .into_iter()
+        .map(|problem| {
+            let solved_count = solved
+                .get(&(problem.contest_id, problem.index.as_str()))
+                .copied()
+                .unwrap_or(0);
+            ProblemWithStats {
+                problem,
+                solved_count,
+            }
+        })
+        .collect()
+}
+
 /// A team member.
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -257,8 +1018,9 @@ pub struct TeamMember {
     pub handle: String,
 }
 
-#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[non_exhaustive]
 pub enum ParticipantType {
     Contestant,
     Practice,
@@ -298,6 +1060,22 @@ pub struct Party {
     pub start_time_seconds: Option<u64>,
 }
 
+impl Party {
+    /// The name of the party: the team name if there is one, otherwise the
+    /// members' handles separated by commas.
+    pub fn name(&self) -> String {
+        match self.team_name {
+            Some(ref name) => name.clone(),
+            None => self
+                .members
+                .iter()
+                .map(|m| m.handle.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+}
+
 /// Either the result is Preliminary or Final
 #[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -345,8 +1123,37 @@ pub struct RanklistRow {
     pub last_submission_time_seconds: Option<u64>,
 }
 
+impl RanklistRow {
+    /// The time a virtual party started the contest, in seconds since the Unix
+    /// epoch. Returns `None` for non-virtual parties.
+    ///
+    /// The relative times of a virtual party (e.g. `best_submission_time_seconds`,
+    /// or a submission's `relative_time_seconds`) count from this moment rather
+    /// than the contest start, so the real time of a submission is
+    /// `virtual_start + relative_time_seconds`.
+    pub fn virtual_start(&self) -> Option<u64> {
+        match self.party.participant_type {
+            ParticipantType::Virtual => self.party.start_time_seconds,
+            _ => None,
+        }
+    }
+
+    /// The penalty column as displayed in the standings of a contest of the
+    /// given type.
+    ///
+    /// ICPC penalties are in minutes, shown as `h:mm`. Other contests rank by
+    /// points, which are shown instead, e.g. `2300` or `12.5`.
+    pub fn penalty_display(&self, contest_type: ContestType) -> String {
+        match contest_type {
+            ContestType::ICPC => format!("{}:{:02}", self.penalty / 60, self.penalty % 60),
+            _ => self.points.to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[non_exhaustive]
 pub enum Verdict {
     Failed,
     Ok,
@@ -464,70 +1271,530 @@ pub struct Submission {
     pub problem: Problem,
     pub author: Party,
     pub programming_language: String,
+    /// `None` for submissions still in queue, whose verdict is either `null`
+    /// or left out.
     pub verdict: Option<Verdict>,
     #[serde(rename = "testset")]
     pub test_set: SubmissionTestSet,
     pub passed_test_count: u64,
     pub time_consumed_millis: u64,
     pub memory_consumed_bytes: u64,
+    /// The points awarded to the submission. Only reported in some contests,
+    /// e.g. IOI-style ones.
+    pub points: Option<f64>,
 }
 
-/// API methods described on Codeforces API page.
-impl User {
-    /// Returns information about one or several users.
+impl Submission {
+    /// The points awarded to the submission: [`Submission::points`] when the
+    /// API reports it, or else an estimate out of the problem's `points`,
+    /// given the total number of tests of the problem.
     ///
-    /// https://codeforces.com/apiHelp/methods#user.info
-    pub async fn info<T>(client: &Client, handles: &[T]) -> Result<Vec<User>>
-    where
-        T: Borrow<str>,
+    /// The estimate assumes all tests are equally weighted: an accepted
+    /// submission gets the full points, others get the fraction of tests
+    /// passed. This is only an estimate for IOI-style problems with test
+    /// groups or Codeforces-style time-decayed scoring.
+    ///
+    /// Returns `None` without reported points if the problem has no points or
+    /// `total_tests` is 0.
+    pub fn points_awarded(&self, total_tests: u64) -> Option<f64> {
+        if let Some(points) = self.points {
+            return Some(points);
+        }
+        let points = self.problem.points?;
+        if self.verdict == Some(Verdict::Ok) {
+            return Some(points);
+        }
+        if total_tests == 0 {
+            return None;
+        }
+        let passed = self.passed_test_count.min(total_tests);
+        Some(points * passed as f64 / total_tests as f64)
+    }
+
+    /// The fraction of tests passed, from 0 to 1.
+    ///
+    /// The API does not report the number of tests, so the total is only
+    /// known once the submission is accepted, with every test passed: this
+    /// returns `Some(1.0)` then. While testing, and for other verdicts where
+    /// judging stops at the first failed test, this returns `None`.
+    pub fn passed_fraction(&self) -> Option<f64> {
+        match self.verdict {
+            Some(Verdict::Ok) => Some(1.0),
+            _ => None,
+        }
+    }
+
+    /// Whether the submission was judged on the final (system) tests,
+    /// as opposed to e.g. the pretests during a contest.
+    pub fn is_systest(&self) -> bool {
+        self.test_set == SubmissionTestSet::Tests
+    }
+
+    /// Whether the submission was made by an official contestant and judged
+    /// on the final tests, as opposed to practice, virtual or
+    /// out-of-competition submissions.
+    pub fn is_official(&self) -> bool {
+        self.author.participant_type == ParticipantType::Contestant && self.is_systest()
+    }
+
+    /// Whether the submission was made in the `window_seconds` before
+    /// `now_seconds`, both ends included.
+    pub fn submitted_within(&self, now_seconds: u64, window_seconds: u64) -> bool {
+        let t = self.creation_time_seconds;
+        now_seconds.saturating_sub(window_seconds) <= t && t <= now_seconds
+    }
+
+    /// Compares submissions by creation time, oldest first.
+    /// Submissions created in the same second are ordered by id.
+    pub fn cmp_by_creation(&self, other: &Submission) -> std::cmp::Ordering {
+        (self.creation_time_seconds, self.id).cmp(&(other.creation_time_seconds, other.id))
+    }
+}
+
+/// The number of submissions it took to get accepted, counting up to and
+/// including the first accepted one. Returns `None` if none was accepted.
+///
+/// `subs` should be the submissions to a single problem; they are ordered by
+/// creation time first, see [`Submission::cmp_by_creation`].
+pub fn attempts_until_ac(subs: &[Submission]) -> Option<usize> {
+    let mut subs: Vec<_> = subs.iter().collect();
+    subs.sort_by(|a, b| a.cmp_by_creation(b));
+    subs.iter()
+        .position(|s| s.verdict == Some(Verdict::Ok))
+        .map(|i| i + 1)
+}
+
+/// The best accepted submission to a problem: the fastest one, then the one
+/// using the least memory, then the earliest one.
+///
+/// Returns `None` if no submission to the problem was accepted.
+pub fn best_submission_for_problem(
+    subs: &[Submission],
+    contest_id: impl Into<ContestId>,
+    index: impl Into<ProblemIndex>,
+) -> Option<&Submission> {
+    let (ContestId(id), ProblemIndex(index)) = (contest_id.into(), index.into());
+    subs.iter()
+        .filter(|s| s.verdict == Some(Verdict::Ok))
+        .filter(|s| s.problem.contest_id == Some(id) && s.problem.index == index)
+        .min_by(|a, b| {
+            (a.time_consumed_millis, a.memory_consumed_bytes)
+                .cmp(&(b.time_consumed_millis, b.memory_consumed_bytes))
+                .then_with(|| a.cmp_by_creation(b))
+        })
+}
+
+/// Submissions are equal when they have the same id.
+impl PartialEq for Submission {
+    fn eq(&self, other: &Submission) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Submission {}
+
+impl std::hash::Hash for Submission {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[non_exhaustive]
+pub enum HackVerdict {
+    HackSuccessful,
+    HackUnsuccessful,
+    InvalidInput,
+    GeneratorIncompilable,
+    GeneratorCrashed,
+    Ignored,
+    Testing,
+    Other,
+}
+
+/// The judge's report on a hack.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct JudgeProtocol {
+    /// `"true"` if the hack's test was entered manually, `"false"` otherwise.
+    pub manual: String,
+    /// A human-readable, localized description of the judging.
+    pub protocol: String,
+    /// A human-readable, localized description of the verdict.
+    pub verdict: String,
+}
+
+impl JudgeProtocol {
+    /// Whether the hack's test was entered manually.
+    pub fn is_manual(&self) -> bool {
+        self.manual == "true"
+    }
+}
+
+/// Represents a hack, made during a Codeforces Round.
+///
+/// https://codeforces.com/apiHelp/objects#Hack
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Hack {
+    pub id: u64,
+    pub creation_time_seconds: u64,
+    pub hacker: Party,
+    pub defender: Party,
+    /// `None` while the hack is being judged.
+    pub verdict: Option<HackVerdict>,
+    pub problem: Problem,
+    /// The test of the hack. Left out when the test is too large.
+    pub test: Option<String>,
+    pub judge_protocol: Option<JudgeProtocol>,
+}
+
+/// Represents a blog entry.
+///
+/// https://codeforces.com/apiHelp/objects#BlogEntry
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BlogEntry {
+    pub id: u64,
+    pub original_locale: String,
+    pub creation_time_seconds: u64,
+    pub author_handle: String,
+    /// The title, in HTML.
+    pub title: String,
+    /// The content, in HTML. Only sent with the full entry.
+    pub content: Option<String>,
+    pub locale: String,
+    pub modification_time_seconds: u64,
+    pub allow_view_history: bool,
+    pub tags: Vec<String>,
+    pub rating: i64,
+}
+
+/// Represents a comment on a blog entry.
+///
+/// https://codeforces.com/apiHelp/objects#Comment
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Comment {
+    pub id: u64,
+    pub creation_time_seconds: u64,
+    pub commentator_handle: String,
+    pub locale: String,
+    /// The text, in HTML.
+    pub text: String,
+    /// The comment this one replies to, if any.
+    pub parent_comment_id: Option<u64>,
+    pub rating: i64,
+}
+
+/// Removes submissions with the same id, keeping the first of each, e.g. when
+/// merging pages that overlap.
+pub fn dedup_submissions(subs: Vec<Submission>) -> Vec<Submission> {
+    let mut seen = std::collections::HashSet::new();
+    subs.into_iter().filter(|s| seen.insert(s.id)).collect()
+}
+
+/// Sorts submissions by creation time, oldest first.
+/// See [`Submission::cmp_by_creation`].
+///
+/// The API returns submissions newest first; this puts them in chronological order.
+pub fn sort_by_creation(subs: &mut [Submission]) {
+    subs.sort_by(Submission::cmp_by_creation)
+}
+
+/// Keeps only the submissions judged on the final (system) tests.
+/// See [`Submission::is_systest`].
+pub fn systest_submissions(subs: &[Submission]) -> Vec<&Submission> {
+    subs.iter().filter(|s| s.is_systest()).collect()
+}
+
+/// Keeps only the submissions made between `from_seconds` and `to_seconds`,
+/// both inclusive.
+pub fn submissions_between(
+    subs: &[Submission],
+    from_seconds: u64,
+    to_seconds: u64,
+) -> Vec<&Submission> {
+    subs.iter()
+        .filter(|s| (from_seconds..=to_seconds).contains(&s.creation_time_seconds))
+        .collect()
+}
+
+/// Keeps only the submissions made in the given contest.
+/// See [`Contest::user_status`] to filter on the server instead.
+pub fn filter_by_contest(
+    subs: &[Submission],
+    contest_id: impl Into<ContestId>,
+) -> Vec<&Submission> {
+    let ContestId(id) = contest_id.into();
+    subs.iter().filter(|s| s.contest_id == Some(id)).collect()
+}
+
+/// Counts the problems solved with each tag, using the tags embedded in each
+/// submission's `problem`.
+///
+/// Only accepted submissions are counted, and each problem is counted once
+/// no matter how many times it was accepted.
+pub fn tag_solve_counts(subs: &[Submission]) -> std::collections::HashMap<String, usize> {
+    let mut seen = std::collections::HashSet::new();
+    let mut counts = std::collections::HashMap::new();
+    for sub in subs {
+        if sub.verdict != Some(Verdict::Ok) || !seen.insert(sub.problem.identity()) {
+            continue;
+        }
+        for tag in &sub.problem.tags {
+            *counts.entry(tag.clone()).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// A summary of a user's activity, computed from their submissions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserActivity {
+    /// The number of submissions.
+    pub submissions: usize,
+    /// The number of accepted submissions.
+    pub accepted: usize,
+    /// The number of distinct problems with an accepted submission.
+    pub solved: usize,
+    /// The creation time of the oldest submission.
+    pub first_submission_seconds: Option<u64>,
+    /// The creation time of the newest submission.
+    pub last_submission_seconds: Option<u64>,
+    /// The number of submissions in each programming language.
+    pub languages: std::collections::HashMap<String, usize>,
+}
+
+impl From<&[Submission]> for UserActivity {
+    fn from(subs: &[Submission]) -> Self {
+        let mut solved = std::collections::HashSet::new();
+        let mut activity = UserActivity {
+            submissions: subs.len(),
+            ..Default::default()
+        };
+        for sub in subs {
+            if sub.verdict == Some(Verdict::Ok) {
+                activity.accepted += 1;
+                solved.insert(sub.problem.identity());
+            }
+            let time = sub.creation_time_seconds;
+            let first = activity
+                .first_submission_seconds
+                .map_or(time, |t| t.min(time));
+            activity.first_submission_seconds = Some(first);
+            activity.last_submission_seconds = Some(time).max(activity.last_submission_seconds);
+            *activity
+                .languages
+                .entry(sub.programming_language.clone())
+                .or_insert(0) += 1;
+        }
+        activity.solved = solved.len();
+        activity
+    }
+}
+
+impl From<Vec<Submission>> for UserActivity {
+    fn from(subs: Vec<Submission>) -> Self {
+        subs.as_slice().into()
+    }
+}
+
+/// Streams the items of a method paged with `from` and `count`, such as
+/// `user.status` or `contest.standings`.
+///
+/// `page_fn(from, count)` fetches the `count` items starting at the 1-based
+/// index `from`. Pages are fetched one after another, `page_size` items at a
+/// time, until a page comes back with fewer items. An error is yielded and
+/// ends the stream.
+pub fn paginate<T, F, Fut>(page_fn: F, page_size: u64) -> impl Stream<Item = Result<T>>
+where
+    F: FnMut(u64, u64) -> Fut,
+    Fut: Future<Output = Result<Vec<T>>>,
+{
+    pages(page_fn, page_size).flat_map(|page| {
+        let items: Vec<_> = match page {
+            Ok(page) => page.into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
+        };
+        stream::iter(items)
+    })
+}
+
+/// Like [`paginate`], but streams whole pages rather than items.
+fn pages<T, F, Fut>(page_fn: F, page_size: u64) -> impl Stream<Item = Result<Vec<T>>>
+where
+    F: FnMut(u64, u64) -> Fut,
+    Fut: Future<Output = Result<Vec<T>>>,
+{
+    let page_size = page_size.max(1);
+    stream::unfold((page_fn, Some(1)), move |(mut page_fn, from)| async move {
+        let from = from?;
+        let page = page_fn(from, page_size).await;
+        let next = match page {
+            Ok(ref page) if page.len() as u64 >= page_size => Some(from + page_size),
+            _ => None,
+        };
+        Some((page, (page_fn, next)))
+    })
+}
+
+/// Ends `stream` as soon as `token` is cancelled, e.g. to stop
+/// [`Contest::watch_standings`] or a [`paginate`]d stream.
+///
+/// The stream is dropped right away, even in the middle of a request or of a
+/// wait between polls, which gives back any rate-limit slot it held.
+pub fn cancellable<S: Stream>(stream: S, token: CancellationToken) -> impl Stream<Item = S::Item> {
+    stream::unfold(
+        (Box::pin(stream), token),
+        |(mut stream, token)| async move {
+            use futures_util::future::{select, Either};
+            let item = match select(Box::pin(token.cancelled()), stream.next()).await {
+                Either::Left(_) => None,
+                Either::Right((item, _)) => item,
+            };
+            item.map(|item| (item, (stream, token)))
+        },
+    )
+}
+
+/// API methods described on Codeforces API page.
+impl User {
+    /// Returns information about one or several users.
+    ///
+    /// The users are in the same order as `handles`, so the two can be
+    /// zipped together. Handles are matched case-insensitively, as the
+    /// returned handles have their canonical case.
+    ///
+    /// https://codeforces.com/apiHelp/methods#user.info
+    pub async fn info<T>(client: &Client, handles: &[T]) -> Result<Vec<User>>
+    where
+        T: Borrow<str>,
     {
-        let users: CFResult<_> = client
-            .borrow()
-            .await
-            .get("https://codeforces.com/api/user.info")
-            .query(&[("handles", handles.join(";"))])
-            .send()
-            .await?
-            .json()
-            .await?;
-        users.into()
+        let query = [("handles", handles.join(";"))];
+        for handle in handles {
+            validate_handle(handle.borrow()).map_err(|e| e.with_call("user.info", &query))?;
+        }
+        let mut users: Vec<User> = client.get("user.info", &query).await?;
+        let mut positions = std::collections::HashMap::new();
+        for (i, h) in handles.iter().enumerate() {
+            positions
+                .entry(h.borrow().to_ascii_lowercase())
+                .or_insert(i);
+        }
+        // Users that match no handle, if any, are kept at the end.
+        users.sort_by_cached_key(|u| {
+            positions
+                .get(&u.handle.to_ascii_lowercase())
+                .copied()
+                .unwrap_or(usize::MAX)
+        });
+        Ok(users)
+    }
+
+    /// Like [`User::info`], but tolerates handles that do not exist.
+    ///
+    /// Returns the users that were found, along with the list of handles that
+    /// are invalid or do not exist. Errors other than a user not being found
+    /// are still returned.
+    pub async fn info_partial<T>(client: &Client, handles: &[T]) -> Result<(Vec<User>, Vec<String>)>
+    where
+        T: Borrow<str>,
+    {
+        let handles = handles.iter().map(|h| h.borrow().to_owned()).collect();
+        isolate_invalid_handles(handles, |hs| async move { User::info(client, &hs).await }).await
+    }
+
+    /// Like [`User::info`] for a single handle, but follows renames: if
+    /// `handle` is not found, the current handle is looked up from the
+    /// redirect of the old profile page, and fetched instead.
+    ///
+    /// This relies on the website rather than the API, and stops working
+    /// whenever profile pages change. Only available with the `scrape` feature.
+    #[cfg(feature = "scrape")]
+    pub async fn info_following_renames(client: &Client, handle: &str) -> Result<User> {
+        let first = |users: Vec<User>| {
+            users
+                .into_iter()
+                .next()
+                .ok_or_else(|| Error::Codeforces("no user returned".into()))
+        };
+        match Self::info(client, &[handle]).await {
+            Err(Error::Codeforces(e)) if e.comment.contains("not found") => {
+                match client.profile_redirect(handle).await? {
+                    Some(renamed) => first(Self::info(client, &[renamed.as_str()]).await?),
+                    None => Err(Error::Codeforces(e)),
+                }
+            }
+            users => first(users?),
+        }
     }
 
     /// Returns the list users who have participated in at least one rated contest.
     ///
     /// The return list of Users are sorted by decreasing order of rating.
+    /// The response is parsed with `simd-json` with the feature of that name.
     ///
     /// https://codeforces.com/apiHelp/methods#user.ratedList
     pub async fn rated_list(client: &Client, active_only: bool) -> Result<Vec<User>> {
-        let users: CFResult<_> = client
-            .borrow()
+        let query = [("activeOnly", active_only.to_string())];
+        client
+            .get_with("user.ratedList", &query, |raw| decode_bulk_response(&raw))
             .await
-            .get("https://codeforces.com/api/user.ratedList")
-            .query(&[("activeOnly", active_only)])
-            .send()
-            .await?
-            .json()
-            .await?;
-        users.into()
+    }
+
+    /// Like [`User::rated_list`], but decodes the users one at a time as the
+    /// stream is polled, instead of collecting them all.
+    ///
+    /// The raw response is still downloaded in full, but it takes much less
+    /// memory than the decoded users: callers keeping only a few of them
+    /// never hold the whole list. The request is sent on the first poll.
+    pub fn rated_list_stream(
+        client: &Client,
+        active_only: bool,
+    ) -> impl Stream<Item = Result<User>> + '_ {
+        let query = [("activeOnly", active_only.to_string())];
+        stream::once(async move {
+            client
+                .get_with("user.ratedList", &query, ResultItems::new)
+                .await
+        })
+        .flat_map(|r| match r {
+            Ok(items) => stream::iter(items).left_stream(),
+            Err(e) => stream::iter(Some(Err(e))).right_stream(),
+        })
     }
 
     /// Returns rating history of the specified user.
     ///
     /// https://codeforces.com/apiHelp/methods#user.rating
     pub async fn rating(client: &Client, handle: &str) -> Result<Vec<RatingChange>> {
-        let changes: CFResult<_> = client
-            .borrow()
-            .await
-            .get("https://codeforces.com/api/user.rating")
-            .query(&[("handle", handle)])
-            .send()
-            .await?
-            .json()
-            .await?;
-        changes.into()
+        let query = [("handle", handle.to_owned())];
+        validate_handle(handle).map_err(|e| e.with_call("user.rating", &query))?;
+        client.get("user.rating", &query).await
     }
 
-    /// Returns submissions of specified user.
+    /// Returns the most recent rating change of the specified user,
+    /// or `None` if the user is unrated.
+    pub async fn latest_rating_change(
+        client: &Client,
+        handle: &str,
+    ) -> Result<Option<RatingChange>> {
+        Ok(Self::rating(client, handle).await?.pop())
+    }
+
+    /// Returns the current rating of the specified user,
+    /// or `None` if the user is unrated.
+    pub async fn current_rating(client: &Client, handle: &str) -> Result<Option<i64>> {
+        let users = Self::info(client, &[handle]).await?;
+        Ok(users.into_iter().next().and_then(|u| u.rating))
+    }
+
+    /// Returns submissions of specified user, newest first.
+    /// See [`sort_by_creation`] for the chronological order.
+    ///
+    /// Returns `count` submissions starting from the `from`-th one, see
+    /// [`UserStatusBuilder::limit`].
     ///
     /// https://codeforces.com/apiHelp/methods#user.status
     pub async fn status(
@@ -536,25 +1803,230 @@ impl User {
         from: u64,
         count: u64,
     ) -> Result<Vec<Submission>> {
-        let submissions: CFResult<_> = client
-            .borrow()
-            .await
-            .get("https://codeforces.com/api/user.status")
-            .query(&[("handle", handle)])
-            .query(&[("from", from.max(1)), ("count", count.min(1))])
-            .send()
-            .await?
-            .json()
-            .await?;
-        submissions.into()
+        Self::status_with(client, handle, |b| b.limit(from, count)).await
+    }
+
+    /// Fetches the whole submission history of the specified user, newest
+    /// first, one page of [`IMPORT_PAGE_SIZE`] submissions after another.
+    ///
+    /// `progress` is called after each page with the number of submissions
+    /// fetched so far, e.g. to show a progress bar. Pages go through the rate
+    /// limit like any other call. If a page fails, the submissions fetched
+    /// until then are returned along with the error.
+    pub async fn import_all_submissions(
+        client: &Client,
+        handle: &str,
+        progress: impl FnMut(usize),
+    ) -> (Vec<Submission>, Option<Error>) {
+        import_pages(
+            |from, count| Self::status(client, handle, from, count),
+            IMPORT_PAGE_SIZE,
+            progress,
+        )
+        .await
+    }
+
+    /// Returns submissions of specified user, newest first, with the given
+    /// options. Without a limit, all submissions are returned.
+    ///
+    /// https://codeforces.com/apiHelp/methods#user.status
+    pub async fn status_with(
+        client: &Client,
+        handle: &str,
+        opts: impl FnOnce(&mut UserStatusBuilder) -> &mut UserStatusBuilder,
+    ) -> Result<Vec<Submission>> {
+        let mut b = UserStatusBuilder::default();
+        opts(&mut b);
+        let mut query = vec![("handle", handle.to_owned())];
+        query.extend(Vec::<(&'static str, String)>::from(b));
+        validate_handle(handle).map_err(|e| e.with_call("user.status", &query))?;
+        client.get("user.status", &query).await
     }
 }
 
+/// Fetches `handles` with `fetch`, binary-splitting the handle set whenever
+/// Codeforces reports that some user is not found, until the offending handles
+/// are isolated.
+///
+/// Returns the users found, in input order, and the invalid handles.
+async fn isolate_invalid_handles<F, Fut>(
+    handles: Vec<String>,
+    fetch: F,
+) -> Result<(Vec<User>, Vec<String>)>
+where
+    F: Fn(Vec<String>) -> Fut,
+    Fut: std::future::Future<Output = Result<Vec<User>>>,
+{
+    let (handles, mut invalid): (Vec<_>, Vec<_>) = handles
+        .into_iter()
+        .partition(|h| validate_handle(h).is_ok());
+    let mut users = Vec::new();
+    let mut not_found = Vec::new();
+    // Groups are popped from the back, so keep the earliest group last.
+    let mut stack = vec![handles];
+    while let Some(mut group) = stack.pop() {
+        if group.is_empty() {
+            continue;
+        }
+        match fetch(group.clone()).await {
+            Ok(found) => users.extend(found),
+            Err(Error::Codeforces(ref e)) if e.comment.contains("not found") => {
+                if group.len() == 1 {
+                    not_found.extend(group);
+                } else {
+                    let second = group.split_off(group.len() / 2);
+                    stack.push(second);
+                    stack.push(group);
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    invalid.extend(not_found);
+    Ok((users, invalid))
+}
+
+/// The number of submissions fetched per request by
+/// [`User::import_all_submissions`].
+pub const IMPORT_PAGE_SIZE: u64 = 1000;
+
+/// Fetches pages of submissions with `page_fn(from, count)` until a short
+/// page, reporting the running count to `progress` after each page.
+///
+/// Submissions already fetched, e.g. when new submissions shift the pages,
+/// are skipped. The first error stops the import.
+async fn import_pages<F, Fut>(
+    page_fn: F,
+    page_size: u64,
+    mut progress: impl FnMut(usize),
+) -> (Vec<Submission>, Option<Error>)
+where
+    F: FnMut(u64, u64) -> Fut,
+    Fut: Future<Output = Result<Vec<Submission>>>,
+{
+    let mut seen = std::collections::HashSet::new();
+    let mut subs = Vec::new();
+    let pages = pages(page_fn, page_size);
+    futures_util::pin_mut!(pages);
+    while let Some(page) = pages.next().await {
+        match page {
+            Ok(page) => subs.extend(page.into_iter().filter(|s| seen.insert(s.id))),
+            Err(e) => return (subs, Some(e)),
+        }
+        progress(subs.len());
+    }
+    (subs, None)
+}
+
+/// Decodes the items of the `result` array of a raw response one at a time.
+struct ResultItems<T> {
+    raw: Vec<u8>,
+    /// The offset in `raw` of the next item, or of the closing bracket.
+    pos: usize,
+    done: bool,
+    item: std::marker::PhantomData<T>,
+}
+
+impl<T> ResultItems<T> {
+    /// Finds the `result` array of the raw response `raw`, or the error
+    /// sent back instead, as in [`decode_response`].
+    fn new(raw: Vec<u8>) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct Envelope<'a> {
+            #[serde(borrow)]
+            result: Option<&'a serde_json::value::RawValue>,
+            comment: Option<String>,
+        }
+        let pos = {
+            let envelope = serde_json::from_slice::<Envelope>(&raw)?;
+            let json = match envelope.result {
+                Some(result) => result.get(),
+                None => return Err(envelope_error(envelope.comment)),
+            };
+            if !json.starts_with('[') {
+                return Err(Error::Decode(serde::de::Error::custom("expected an array")));
+            }
+            // Right after the opening bracket.
+            json.as_ptr() as usize - raw.as_ptr() as usize + 1
+        };
+        Ok(Self {
+            raw,
+            pos,
+            done: false,
+            item: std::marker::PhantomData,
+        })
+    }
+}
+
+impl<T: for<'t> Deserialize<'t>> Iterator for ResultItems<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        if self.done {
+            return None;
+        }
+        let start = self.pos
+            + self.raw[self.pos..]
+                .iter()
+                .take_while(|&&b| b.is_ascii_whitespace() || b == b',')
+                .count();
+        if matches!(self.raw.get(start), None | Some(b']')) {
+            self.done = true;
+            return None;
+        }
+        let mut items = serde_json::Deserializer::from_slice(&self.raw[start..]).into_iter::<T>();
+        let item = items.next()?;
+        self.pos = start + items.byte_offset();
+        self.done = item.is_err();
+        Some(item.map_err(Error::from))
+    }
+}
+
+/// The range of items requested from a paged method, shared by the builders.
+///
+/// Items are 1-indexed: a `from` of 0 is treated as 1. A `count` of 0 means
+/// no limit is applied. When unset, neither is sent, and every item from the
+/// first one is returned.
+#[derive(Debug, Default, Clone, Copy)]
+struct Limit {
+    from: Option<u64>,
+    count: Option<u64>,
+}
+
+impl Limit {
+    fn new(from: u64, count: u64) -> Self {
+        Self {
+            from: Some(from.max(1)),
+            count: Some(count).filter(|&c| c > 0),
+        }
+    }
+
+    /// Keeps the items in the range, out of all items from the first one.
+    fn apply<T>(self, items: Vec<T>) -> Vec<T> {
+        let skip = self.from.map_or(0, |from| from - 1) as usize;
+        let take = self.count.map_or(usize::MAX, |count| count as usize);
+        items.into_iter().skip(skip).take(take).collect()
+    }
+
+    /// The `from` and `count` query parameters, when set.
+    fn query(self) -> impl Iterator<Item = (&'static str, String)> {
+        let from = self.from.map(|v| ("from", v.to_string()));
+        let count = self.count.map(|v| ("count", v.to_string()));
+        from.into_iter().chain(count)
+    }
+}
+
+/// The most handles sent in one `contest.standings` request, see
+/// [`ContestRankingsBuilder::handles`].
+///
+/// Codeforces accepts about 10000 handles, but request URLs are limited to
+/// 64KiB, which fits 2000 handles of the maximum length.
+pub const MAX_STANDINGS_HANDLES: usize = 2000;
+
 /// Build a contest ranking request.
 #[derive(Debug, Default)]
 pub struct ContestRankingsBuilder {
-    from: Option<u64>,
-    count: Option<u64>,
+    limit: Limit,
     handles: Option<Vec<String>>,
     room: Option<u64>,
     allow_unofficial: bool,
@@ -562,13 +2034,20 @@ pub struct ContestRankingsBuilder {
 
 impl ContestRankingsBuilder {
     /// Put a limit on the number of records returned.
+    ///
+    /// Rows are 1-indexed: a `from` of 0 is treated as 1.
+    /// A `count` of 0 means no limit is applied.
     pub fn limit(&mut self, from: u64, count: u64) -> &mut Self {
-        self.from = Some(from);
-        self.count = Some(count);
+        self.limit = Limit::new(from, count);
         self
     }
 
     /// Set a list of handles.
+    ///
+    /// At most [`MAX_STANDINGS_HANDLES`] handles are sent per request.
+    /// Longer lists are split into batches by [`Contest::standings`],
+    /// which fetches each batch in full and merges the rows, dropping
+    /// duplicate parties. The limit then applies once, to the merged rows.
     pub fn handles(&mut self, handles: Vec<String>) -> &mut Self {
         self.handles = Some(handles);
         self
@@ -587,19 +2066,80 @@ impl ContestRankingsBuilder {
     }
 }
 
+impl ContestRankingsBuilder {
+    /// Builds the `contest.standings` queries for `contest_id` with the given
+    /// options, one per batch of at most [`MAX_STANDINGS_HANDLES`] handles.
+    /// Always returns at least one query.
+    ///
+    /// Batches are fetched without a limit, which is returned instead, to be
+    /// applied to the merged rows.
+    fn queries(
+        contest_id: ContestId,
+        opts: impl FnOnce(&mut ContestRankingsBuilder) -> &mut ContestRankingsBuilder,
+    ) -> (Vec<Vec<(&'static str, String)>>, Limit) {
+        let mut b = ContestRankingsBuilder::default();
+        opts(&mut b);
+        let handles = match b.handles.take() {
+            Some(handles) if handles.len() > MAX_STANDINGS_HANDLES => handles,
+            handles => {
+                b.handles = handles;
+                return (vec![b.into_query(contest_id)], Limit::default());
+            }
+        };
+        let queries = handles
+            .chunks(MAX_STANDINGS_HANDLES)
+            .map(|chunk| {
+                ContestRankingsBuilder {
+                    limit: Limit::default(),
+                    handles: Some(chunk.to_vec()),
+                    room: b.room,
+                    allow_unofficial: b.allow_unofficial,
+                }
+                .into_query(contest_id)
+            })
+            .collect();
+        (queries, b.limit)
+    }
+
+    fn into_query(self, contest_id: ContestId) -> Vec<(&'static str, String)> {
+        let mut query = vec![("contestId", contest_id.to_string())];
+        query.extend(Vec::<(&'static str, String)>::from(self));
+        query
+    }
+}
+
 /// Consumes self and return a query list.
 impl From<ContestRankingsBuilder> for Vec<(&'static str, String)> {
     fn from(c: ContestRankingsBuilder) -> Self {
-        vec![
-            Some(("allowOfficial", c.allow_unofficial.to_string())),
-            c.from.map(|v| ("from", v.to_string())),
-            c.count.map(|v| ("count", v.to_string())),
-            c.handles.map(|v| ("handles", v.join(";"))),
-            c.room.map(|v| ("room", v.to_string())),
-        ]
-        .into_iter()
-        .filter_map(|v| v)
-        .collect()
+        let mut query = vec![("allowOfficial", c.allow_unofficial.to_string())];
+        query.extend(c.limit.query());
+        query.extend(c.handles.map(|v| ("handles", v.join(";"))));
+        query.extend(c.room.map(|v| ("room", v.to_string())));
+        query
+    }
+}
+
+/// Build a `user.status` request.
+#[derive(Debug, Default)]
+pub struct UserStatusBuilder {
+    limit: Limit,
+}
+
+impl UserStatusBuilder {
+    /// Put a limit on the number of submissions returned.
+    ///
+    /// Submissions are 1-indexed: a `from` of 0 is treated as 1.
+    /// A `count` of 0 means no limit is applied.
+    pub fn limit(&mut self, from: u64, count: u64) -> &mut Self {
+        self.limit = Limit::new(from, count);
+        self
+    }
+}
+
+/// Consumes self and return a query list.
+impl From<UserStatusBuilder> for Vec<(&'static str, String)> {
+    fn from(b: UserStatusBuilder) -> Self {
+        b.limit.query().collect()
     }
 }
 
@@ -607,49 +2147,241 @@ impl From<ContestRankingsBuilder> for Vec<(&'static str, String)> {
 impl Contest {
     /// Gets a list of all contests.
     pub async fn list(client: &Client, with_gym: bool) -> Result<Vec<Contest>> {
-        let v: CFResult<_> = client
-            .borrow()
+        client
+            .get("contest.list", &[("gym", with_gym.to_string())])
             .await
-            .get("https://codeforces.com/api/contest.list")
-            .query(&[("gym", with_gym)])
-            .send()
-            .await?
-            .json()
-            .await?;
-        v.into()
+    }
+
+    /// Gets a list of all contests of the given kind.
+    ///
+    /// Official contests can be found both among regular and gym contests,
+    /// so this takes two requests for [`ContestKind::Official`].
+    pub async fn list_kind(client: &Client, kind: &ContestKind) -> Result<Vec<Contest>> {
+        let mut contests = if *kind == ContestKind::Official {
+            Self::list(client, false).await?
+        } else {
+            vec![]
+        };
+        contests.extend(Self::list(client, true).await?);
+        contests.retain(|c| c.parsed_kind() == *kind);
+        Ok(contests)
+    }
+
+    /// Gets the contests with the given ids, from the contest list rather
+    /// than one request per contest.
+    ///
+    /// Returns the contests found, in the order of `ids`, along with the ids
+    /// that match no contest. Gym contests are only listed if some ids are
+    /// not regular contests, which takes a second request.
+    pub async fn get_many<I>(client: &Client, ids: &[I]) -> Result<(Vec<Contest>, Vec<ContestId>)>
+    where
+        I: Into<ContestId> + Copy,
+    {
+        let ids: Vec<ContestId> = ids.iter().map(|&id| id.into()).collect();
+        let mut contests = Self::list(client, false).await?;
+        if ids.iter().any(|id| !contests.iter().any(|c| c.id == id.0)) {
+            contests.extend(Self::list(client, true).await?);
+        }
+        let mut missing = Vec::new();
+        let found = ids
+            .into_iter()
+            .filter_map(|id| {
+                let contest = contests.iter().find(|c| c.id == id.0).cloned();
+                if contest.is_none() {
+                    missing.push(id);
+                }
+                contest
+            })
+            .collect();
+        Ok((found, missing))
+    }
+
+    /// Returns the submissions of the specified user in a contest, newest first.
+    ///
+    /// https://codeforces.com/apiHelp/methods#contest.status
+    pub async fn user_status(
+        client: &Client,
+        contest_id: impl Into<ContestId>,
+        handle: &str,
+    ) -> Result<Vec<Submission>> {
+        let query = [
+            ("contestId", contest_id.into().to_string()),
+            ("handle", handle.to_owned()),
+        ];
+        validate_handle(handle).map_err(|e| e.with_call("contest.status", &query))?;
+        client.get("contest.status", &query).await
     }
 
     /// Gets the standings of a contest.
     ///
+    /// The result can be converted into [`Standings`] with `.into()`.
+    /// Standings of finished contests can be cached, see
+    /// [`ClientBuilder::cache_finished_standings`].
+    ///
+    /// Long lists of handles are fetched in batches, see
+    /// [`ContestRankingsBuilder::handles`].
+    ///
     /// https://codeforces.com/apiHelp/methods#contest.standings
     pub async fn standings(
         client: &Client,
-        contest_id: u64,
+        contest_id: impl Into<ContestId>,
         opts: impl FnOnce(&mut ContestRankingsBuilder) -> &mut ContestRankingsBuilder,
     ) -> Result<(Contest, Vec<Problem>, Vec<RanklistRow>)> {
+        let (queries, limit) = ContestRankingsBuilder::queries(contest_id.into(), opts);
+        let mut v = client.get_standings(&queries[0]).await?;
+        for query in &queries[1..] {
+            v.merge_rows(client.get_standings(query).await?);
+        }
+        v.rows = limit.apply(v.rows);
+        Ok(v.into())
+    }
+
+    /// Gets the top `n` rows of the standings of a contest.
+    ///
+    /// Only the requested rows are downloaded, which is much lighter than
+    /// the full standings of a large contest.
+    pub async fn top_standings(
+        client: &Client,
+        contest_id: impl Into<ContestId>,
+        n: u64,
+    ) -> Result<(Contest, Vec<Problem>, Vec<RanklistRow>)> {
+        // A count of 0 would fetch every row.
+        let (contest, problems, mut rows) =
+            Self::standings(client, contest_id, |b| b.limit(1, n.max(1))).await?;
+        rows.truncate(n as usize);
+        Ok((contest, problems, rows))
+    }
+
+    /// Polls the standings of a contest every `interval`.
+    ///
+    /// The first successful poll is always yielded. After that, standings are
+    /// only yielded when some row's rank or points changed since the last
+    /// yielded standings (see [`Standings::changed_rows`]). Polling stops once
+    /// the contest phase reaches [`ContestPhase::Finished`]. Errors are yielded
+    /// and polling goes on. Every poll but the first waits for `interval`,
+    /// whether the previous one failed or not.
+    ///
+    /// The yielded standings are shared with the stream, which compares the
+    /// next poll against them.
+    ///
+    /// See [`ContestPhase::recommended_poll_interval`] to pick `interval`.
+    pub fn watch_standings(
+        client: &Client,
+        contest_id: impl Into<ContestId>,
+        interval: std::time::Duration,
+    ) -> impl Stream<Item = Result<Arc<Standings>>> + '_ {
+        let contest_id = contest_id.into();
+        stream::unfold(
+            (None::<Arc<Standings>>, false, false),
+            move |(last, polled, finished)| async move {
+                if finished {
+                    return None;
+                }
+                let mut polled = polled;
+                loop {
+                    if polled {
+                        rt::sleep(interval).await;
+                    }
+                    polled = true;
+                    let current: Standings = match Self::standings(client, contest_id, |b| b).await
+                    {
+                        Ok(v) => v.into(),
+                        Err(e) => return Some((Err(e), (last, true, false))),
+                    };
+                    let finished = current.contest.phase == ContestPhase::Finished;
+                    let changed = match last {
+                        None => true,
+                        Some(ref last) => !current.changed_rows(last).is_empty(),
+                    };
+                    if changed {
+                        let current = Arc::new(current);
+                        return Some((Ok(current.clone()), (Some(current), true, finished)));
+                    } else if finished {
+                        return None;
+                    }
+                }
+            },
+        )
+    }
+
+    /// Returns the number of official participants of a contest, i.e. the
+    /// number of rows of its standings.
+    ///
+    /// The API does not report the total on its own, so this fetches the full
+    /// standings; expect it to take as long as [`Contest::standings`].
+    pub async fn participant_count(
+        client: &Client,
+        contest_id: impl Into<ContestId>,
+    ) -> Result<u64> {
+        let (_, _, rows) = Self::standings(client, contest_id, |b| b).await?;
+        Ok(rows.len() as u64)
+    }
+}
+
+/// The problemset of Codeforces.
+pub struct Problemset;
+
+/// API methods described on Codeforces API page.
+impl Problemset {
+    /// Returns all problems from the problemset having all of the given tags,
+    /// along with their statistics.
+    ///
+    /// https://codeforces.com/apiHelp/methods#problemset.problems
+    pub async fn problems<T>(
+        client: &Client,
+        tags: &[T],
+    ) -> Result<(Vec<Problem>, Vec<ProblemStatistics>)>
+    where
+        T: Borrow<str>,
+    {
         #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
         struct Middle {
-            contest: Contest,
             problems: Vec<Problem>,
-            rows: Vec<RanklistRow>,
+            problem_statistics: Vec<ProblemStatistics>,
         }
 
-        let mut b = ContestRankingsBuilder::default();
-        opts(&mut b);
+        let query: Vec<_> = if tags.is_empty() {
+            vec![]
+        } else {
+            vec![("tags", tags.join(";"))]
+        };
+        let v: Middle = client.get("problemset.problems", &query).await?;
+        Ok((v.problems, v.problem_statistics))
+    }
 
-        let v: CFResult<Middle> = client
-            .borrow()
-            .await
-            .get("https://codeforces.com/api/contest.standings")
-            .query(&[("contestId", contest_id)])
-            .query(&Vec::<(&'static str, String)>::from(b))
-            .send()
-            .await?
-            .json()
-            .await?;
-        let v: Middle = Result::<_>::from(v)?;
-
-        Ok((v.contest, v.problems, v.rows))
+    /// Like [`Problemset::problems`], but with each problem joined with its
+    /// statistics.
+    pub async fn problems_with_stats<T>(
+        client: &Client,
+        tags: &[T],
+    ) -> Result<Vec<ProblemWithStats>>
+    where
+        T: Borrow<str>,
+    {
+        let (problems, stats) = Self::problems(client, tags).await?;
+        Ok(join_statistics(problems, &stats))
+    }
+
+    /// Returns all problems from the problemset having any of the given tags.
+    ///
+    /// Codeforces only supports filtering by all tags, so this takes one
+    /// request per tag. Problems are deduplicated by [`Problem::identity`].
+    pub async fn problems_any_tag<T>(client: &Client, tags: &[T]) -> Result<Vec<Problem>>
+    where
+        T: Borrow<str>,
+    {
+        let mut seen = std::collections::HashSet::new();
+        let mut problems = Vec::new();
+        for tag in tags {
+            let (found, _) = Self::problems(client, &[tag.borrow()]).await?;
+            for problem in found {
+                if seen.insert((problem.contest_id, problem.index.clone())) {
+                    problems.push(problem);
+                }
+            }
+        }
+        Ok(problems)
     }
 }
 
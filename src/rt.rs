@@ -0,0 +1,77 @@
+//! Timers, background tasks and files, on tokio or, on `wasm32`, in the
+//! browser.
+//!
+//! Files are not available in the browser: reading one finds nothing, and
+//! writing one does nothing.
+use std::future::Future;
+use std::path::Path;
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use std::time::{Instant, SystemTime, UNIX_EPOCH};
+#[cfg(target_arch = "wasm32")]
+pub(crate) use web_time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Waits until `duration` has elapsed.
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(not(target_arch = "wasm32"))]
+    tokio::time::sleep(duration).await;
+    #[cfg(target_arch = "wasm32")]
+    gloo_timers::future::sleep(duration).await;
+}
+
+/// Runs `task` in the background.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn spawn(task: impl Future<Output = ()> + Send + 'static) {
+    tokio::spawn(task);
+}
+
+/// Runs `task` in the background.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn spawn(task: impl Future<Output = ()> + 'static) {
+    wasm_bindgen_futures::spawn_local(task);
+}
+
+/// Runs `future`, giving up with `None` once `duration` has elapsed.
+pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        tokio::time::timeout(duration, future).await.ok()
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        use futures_util::future::{self, Either};
+        futures_util::pin_mut!(future);
+        let sleep = sleep(duration);
+        futures_util::pin_mut!(sleep);
+        match future::select(future, sleep).await {
+            Either::Left((v, _)) => Some(v),
+            Either::Right(_) => None,
+        }
+    }
+}
+
+/// Reads the file at `path`, if there is one.
+pub(crate) async fn read_file(path: &Path) -> Option<Vec<u8>> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        tokio::fs::read(path).await.ok()
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = path;
+        None
+    }
+}
+
+/// Writes `contents` to the file at `path`, ignoring failures.
+pub(crate) async fn write_file(path: &Path, contents: &[u8]) {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let _ = tokio::fs::write(path, contents).await;
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = (path, contents);
+    }
+}
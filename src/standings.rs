@@ -0,0 +1,392 @@
+use super::*;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// The standings of a contest, as returned by `contest.standings`.
+///
+/// `problems` are in display order (see [`problem_index_cmp`]), and each
+/// row's `problem_results` are positionally aligned with them.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Standings {
+    pub contest: Contest,
+    pub problems: Vec<Problem>,
+    pub rows: Vec<RanklistRow>,
+}
+
+impl From<(Contest, Vec<Problem>, Vec<RanklistRow>)> for Standings {
+    fn from((contest, problems, rows): (Contest, Vec<Problem>, Vec<RanklistRow>)) -> Self {
+        Self {
+            contest,
+            problems,
+            rows,
+        }
+    }
+}
+
+impl From<Standings> for (Contest, Vec<Problem>, Vec<RanklistRow>) {
+    fn from(s: Standings) -> Self {
+        (s.contest, s.problems, s.rows)
+    }
+}
+
+impl Standings {
+    /// Writes the standings as CSV.
+    ///
+    /// The columns are rank, party name, total points, penalty, followed by
+    /// the points gained on each problem, headed by the problem indices.
+    pub fn to_csv<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let mut header = vec![
+            "Rank".to_owned(),
+            "Party".to_owned(),
+            "Points".to_owned(),
+            "Penalty".to_owned(),
+        ];
+        header.extend(self.problems.iter().map(|p| p.index.clone()));
+        write_csv_record(&mut w, &header)?;
+
+        for row in &self.rows {
+            let mut record = vec![
+                row.rank.to_string(),
+                row.party.name(),
+                row.points.to_string(),
+                row.penalty.to_string(),
+            ];
+            record.extend(row.problem_results.iter().map(|r| r.points.to_string()));
+            write_csv_record(&mut w, &record)?;
+        }
+        Ok(())
+    }
+}
+
+/// Iterates over the rows of [`Standings`], along with each row's results keyed
+/// by problem index.
+///
+/// If a row has more or fewer results than there are problems, the extra
+/// results or problems are left out.
+pub struct StandingsIter<'a> {
+    problems: &'a [Problem],
+    rows: std::slice::Iter<'a, RanklistRow>,
+}
+
+impl<'a> Iterator for StandingsIter<'a> {
+    type Item = (&'a RanklistRow, HashMap<&'a str, &'a ProblemResult>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let row = self.rows.next()?;
+        let results = self
+            .problems
+            .iter()
+            .map(|p| p.index.as_str())
+            .zip(row.problem_results.iter())
+            .collect();
+        Some((row, results))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+impl<'a> IntoIterator for &'a Standings {
+    type Item = (&'a RanklistRow, HashMap<&'a str, &'a ProblemResult>);
+    type IntoIter = StandingsIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        StandingsIter {
+            problems: &self.problems,
+            rows: self.rows.iter(),
+        }
+    }
+}
+
+/// The key identifying a party across standings.
+type PartyKey<'a> = (Option<u64>, Vec<&'a str>, ParticipantType);
+
+fn party_key(party: &Party) -> PartyKey<'_> {
+    (
+        party.team_id,
+        party.members.iter().map(|m| m.handle.as_str()).collect(),
+        party.participant_type,
+    )
+}
+
+impl Standings {
+    /// Returns the rows whose rank or points differ from `previous`,
+    /// including rows of parties that were not present in `previous`.
+    ///
+    /// Rows are matched by party (team, members and participant type).
+    pub fn changed_rows(&self, previous: &Standings) -> Vec<&RanklistRow> {
+        let previous: HashMap<_, _> = previous
+            .rows
+            .iter()
+            .map(|row| (party_key(&row.party), row))
+            .collect();
+        self.rows
+            .iter()
+            .filter(|row| match previous.get(&party_key(&row.party)) {
+                Some(old) => old.rank != row.rank || old.points != row.points,
+                None => true,
+            })
+            .collect()
+    }
+}
+
+impl Standings {
+    /// Adds the rows of `other` whose party is not in the standings yet,
+    /// then sorts the rows by rank.
+    pub(crate) fn merge_rows(&mut self, other: Standings) {
+        let known: HashSet<_> = self.rows.iter().map(|row| party_key(&row.party)).collect();
+        let new: Vec<_> = other
+            .rows
+            .into_iter()
+            .filter(|row| !known.contains(&party_key(&row.party)))
+            .collect();
+        self.rows.extend(new);
+        self.rows.sort_by_key(|row| row.rank);
+    }
+}
+
+/// Distribution of the first accepted times on a problem, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SolveTimes {
+    /// The number of parties that solved the problem.
+    pub count: usize,
+    pub min: u64,
+    pub median: u64,
+    pub max: u64,
+}
+
+impl SolveTimes {
+    /// Computes the distribution over the given times.
+    /// Returns `None` if there are no times.
+    fn from_times(mut times: Vec<u64>) -> Option<Self> {
+        if times.is_empty() {
+            return None;
+        }
+        times.sort_unstable();
+        let n = times.len();
+        let median = if n % 2 == 1 {
+            times[n / 2]
+        } else {
+            (times[n / 2 - 1] + times[n / 2]) / 2
+        };
+        Some(Self {
+            count: n,
+            min: times[0],
+            median,
+            max: times[n - 1],
+        })
+    }
+}
+
+impl Standings {
+    /// Computes the distribution of solve times (`best_submission_time_seconds`)
+    /// for each problem, keyed by problem index.
+    ///
+    /// Rows that did not solve a problem are skipped, and problems that no one
+    /// solved are left out.
+    pub fn solve_times(&self) -> HashMap<String, SolveTimes> {
+        self.problems
+            .iter()
+            .enumerate()
+            .filter_map(|(i, problem)| {
+                let times = self
+                    .rows
+                    .iter()
+                    .filter_map(|row| row.problem_results.get(i))
+                    .filter(|r| r.points > 0.0)
+                    .filter_map(|r| r.best_submission_time_seconds)
+                    .collect();
+                SolveTimes::from_times(times).map(|t| (problem.index.clone(), t))
+            })
+            .collect()
+    }
+
+    /// Counts the rows that solved each problem, keyed by problem index.
+    ///
+    /// Unlike [`Standings::solve_times`], problems that no one solved are
+    /// counted as 0.
+    pub fn solve_counts(&self) -> HashMap<String, u64> {
+        self.problems
+            .iter()
+            .enumerate()
+            .map(|(i, problem)| {
+                let count = self
+                    .rows
+                    .iter()
+                    .filter_map(|row| row.problem_results.get(i))
+                    .filter(|r| r.points > 0.0)
+                    .count();
+                (problem.index.clone(), count as u64)
+            })
+            .collect()
+    }
+}
+
+impl Standings {
+    /// Returns the standings restricted to the problems with the given indices,
+    /// keeping each row's `problem_results` aligned with `problems`.
+    ///
+    /// Problems keep their original order. Unknown indices are ignored.
+    pub fn project<I>(&self, indices: &[I]) -> Standings
+    where
+        I: Into<ProblemIndex> + Clone,
+    {
+        let indices: Vec<ProblemIndex> = indices.iter().cloned().map(Into::into).collect();
+        let keep: Vec<bool> = self
+            .problems
+            .iter()
+            .map(|p| indices.iter().any(|i| i.0 == p.index))
+            .collect();
+        Standings {
+            contest: self.contest.clone(),
+            problems: select(&self.problems, &keep),
+            rows: self
+                .rows
+                .iter()
+                .map(|row| RanklistRow {
+                    party: row.party.clone(),
+                    problem_results: select(&row.problem_results, &keep),
+                    ..*row
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Standings {
+    /// The problem with the given name, compared case-insensitively, or the
+    /// first one if several share the name.
+    pub fn problem_by_name(&self, name: &str) -> Option<&Problem> {
+        let name = name.to_lowercase();
+        self.problems.iter().find(|p| p.name.to_lowercase() == name)
+    }
+}
+
+impl Standings {
+    /// The rows of ghost parties, e.g. the original teams imported into the
+    /// standings of a mirror contest.
+    pub fn ghosts(&self) -> Vec<&RanklistRow> {
+        self.rows.iter().filter(|row| row.party.ghost).collect()
+    }
+
+    /// The rows of parties that are not ghosts. See [`Standings::ghosts`].
+    pub fn live_participants(&self) -> Vec<&RanklistRow> {
+        self.rows.iter().filter(|row| !row.party.ghost).collect()
+    }
+}
+
+impl Standings {
+    /// Groups the rows by team, keyed by `team_id`.
+    ///
+    /// Rows of parties that are not teams are left out.
+    pub fn teams(&self) -> HashMap<u64, Vec<&RanklistRow>> {
+        let mut teams: HashMap<u64, Vec<&RanklistRow>> = HashMap::new();
+        for row in &self.rows {
+            if let Some(id) = row.party.team_id {
+                teams.entry(id).or_default().push(row);
+            }
+        }
+        teams
+    }
+}
+
+/// The longest file name of a cached response, below the 255 bytes most file
+/// systems allow, leaving room for the extension.
+const MAX_FILE_NAME: usize = 200;
+
+/// Caches the standings of finished contests, which never change.
+///
+/// Standings are kept in memory, and optionally as raw responses in a
+/// directory, keyed by the method and query of the request.
+pub(crate) struct StandingsCache {
+    memory: Mutex<HashMap<String, Standings>>,
+    dir: Option<PathBuf>,
+}
+
+impl StandingsCache {
+    pub(crate) fn new(dir: Option<PathBuf>) -> Self {
+        Self {
+            memory: Mutex::new(HashMap::new()),
+            dir,
+        }
+    }
+
+    /// The file caching the response to `key`, named after the
+    /// percent-encoded key so that distinct keys never share a file.
+    ///
+    /// Keys too long for a file name, e.g. with many handles, are only
+    /// cached in memory.
+    fn path(&self, key: &str) -> Option<PathBuf> {
+        let mut name = String::with_capacity(key.len());
+        for b in key.bytes() {
+            if b.is_ascii_alphanumeric() || b"-_.".contains(&b) {
+                name.push(b as char);
+            } else {
+                name.push_str(&format!("%{:02X}", b));
+            }
+        }
+        if name.len() > MAX_FILE_NAME {
+            return None;
+        }
+        Some(self.dir.as_ref()?.join(name + ".json"))
+    }
+
+    /// Returns the cached standings for `key`, looking in the directory if
+    /// they are not in memory.
+    pub(crate) async fn get(&self, key: &str) -> Option<Standings> {
+        if let Some(s) = self.memory.lock().unwrap().get(key) {
+            return Some(s.clone());
+        }
+        let raw = crate::rt::read_file(&self.path(key)?).await?;
+        let standings: Standings = decode_response(&raw).ok()?;
+        self.memory
+            .lock()
+            .unwrap()
+            .insert(key.to_owned(), standings.clone());
+        Some(standings)
+    }
+
+    /// Caches `standings`, decoded from `raw`, if the contest is finished.
+    ///
+    /// Failing to write the directory only skips the on-disk copy.
+    pub(crate) async fn insert(&self, key: &str, standings: &Standings, raw: &[u8]) {
+        if standings.contest.phase != ContestPhase::Finished {
+            return;
+        }
+        self.memory
+            .lock()
+            .unwrap()
+            .insert(key.to_owned(), standings.clone());
+        if let Some(path) = self.path(key) {
+            crate::rt::write_file(&path, raw).await;
+        }
+    }
+}
+
+/// Clones the items whose corresponding `keep` flag is set.
+fn select<T: Clone>(items: &[T], keep: &[bool]) -> Vec<T> {
+    items
+        .iter()
+        .zip(keep)
+        .filter(|(_, &k)| k)
+        .map(|(v, _)| v.clone())
+        .collect()
+}
+
+/// Writes a single CSV record, quoting fields when needed.
+fn write_csv_record<W: Write>(w: &mut W, fields: &[String]) -> io::Result<()> {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            w.write_all(b",")?;
+        }
+        if field.contains(&[',', '"', '\n', '\r'][..]) {
+            write!(w, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            w.write_all(field.as_bytes())?;
+        }
+    }
+    w.write_all(b"\n")
+}
@@ -1,5 +1,7 @@
 use super::*;
 
+mod schema;
+
 #[tokio::test]
 pub async fn user_works() {
     let http = Client::new();
@@ -37,3 +39,2998 @@ pub async fn contest_works() {
     // contest.standings
     Contest::standings(&http, 566, |f| f).await.unwrap();
 }
+
+#[test]
+pub fn handle_validation() {
+    for handle in &["natsukagami", "vjudge2", "tourist", "a.b-c_d", "abc"] {
+        validate_handle(handle).unwrap();
+    }
+    for handle in &[
+        "",
+        "ab",
+        "has space",
+        "semi;colon",
+        "ünicode",
+        &"x".repeat(25),
+    ] {
+        assert!(
+            validate_handle(handle).is_err(),
+            "{:?} should be invalid",
+            handle
+        );
+    }
+}
+
+#[tokio::test]
+pub async fn invalid_handles_short_circuit() {
+    let http = Client::new();
+
+    for handle in &["", "no", "bad/handle", "a;b;c"] {
+        match User::info(&http, &["natsukagami", handle]).await {
+            Err(Error::Codeforces(e)) => {
+                assert!(e.comment.starts_with("invalid handle"));
+                assert_eq!(e.method.as_deref(), Some("user.info"));
+            }
+            v => panic!("expected an invalid handle error, got {:?}", v),
+        }
+        assert!(matches!(
+            User::rating(&http, handle).await,
+            Err(Error::Codeforces(_))
+        ));
+        assert!(matches!(
+            User::status(&http, handle, 1, 10).await,
+            Err(Error::Codeforces(_))
+        ));
+    }
+}
+
+/// The body of a `contest.standings` result.
+const STANDINGS_JSON: &str = r#"{
+    "contest": {
+        "id": 1, "name": "Test Round", "type": "CF", "phase": "FINISHED",
+        "frozen": false, "durationSeconds": 7200, "startTimeSeconds": 1600000000
+    },
+    "problems": [
+        {"contestId": 1, "index": "A", "name": "Alpha", "type": "PROGRAMMING", "points": 500.0, "tags": []},
+        {"contestId": 1, "index": "B", "name": "Beta", "type": "PROGRAMMING", "points": 1000.0, "tags": []},
+        {"contestId": 1, "index": "C", "name": "Gamma", "type": "PROGRAMMING", "points": 1500.0, "tags": []}
+    ],
+    "rows": [
+        {
+            "party": {"contestId": 1, "members": [{"handle": "alice"}], "participantType": "CONTESTANT", "ghost": false},
+            "rank": 1, "points": 2300.0, "penalty": 0, "successfulHackCount": 1, "unsuccessfulHackCount": 0,
+            "problemResults": [
+                {"points": 480.0, "rejectedAttemptCount": 0, "type": "FINAL", "bestSubmissionTimeSeconds": 300},
+                {"points": 820.0, "rejectedAttemptCount": 1, "type": "FINAL", "bestSubmissionTimeSeconds": 1500},
+                {"points": 900.0, "rejectedAttemptCount": 0, "type": "FINAL", "bestSubmissionTimeSeconds": 5400}
+            ]
+        },
+        {
+            "party": {"contestId": 1, "members": [{"handle": "bob"}, {"handle": "carol"}], "participantType": "CONTESTANT",
+                      "teamId": 7, "teamName": "Bob, Carol and \"friends\"", "ghost": false},
+            "rank": 2, "points": 1200.0, "penalty": 0, "successfulHackCount": 0, "unsuccessfulHackCount": 2,
+            "problemResults": [
+                {"points": 450.0, "rejectedAttemptCount": 2, "type": "FINAL", "bestSubmissionTimeSeconds": 600},
+                {"points": 750.0, "rejectedAttemptCount": 0, "type": "FINAL", "bestSubmissionTimeSeconds": 3000},
+                {"points": 0.0, "rejectedAttemptCount": 3, "type": "FINAL"}
+            ]
+        },
+        {
+            "party": {"contestId": 1, "members": [{"handle": "dave"}], "participantType": "CONTESTANT", "ghost": false},
+            "rank": 3, "points": 490.0, "penalty": 0, "successfulHackCount": 0, "unsuccessfulHackCount": 0,
+            "problemResults": [
+                {"points": 490.0, "rejectedAttemptCount": 0, "type": "FINAL", "bestSubmissionTimeSeconds": 120},
+                {"points": 0.0, "rejectedAttemptCount": 0, "type": "FINAL"},
+                {"points": 0.0, "rejectedAttemptCount": 0, "type": "FINAL"}
+            ]
+        }
+    ]
+}"#;
+
+fn standings_fixture() -> Standings {
+    serde_json::from_str(STANDINGS_JSON).unwrap()
+}
+
+#[test]
+pub fn standings_to_csv() {
+    let mut out = Vec::new();
+    standings_fixture().to_csv(&mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<_> = out.lines().collect();
+
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "Rank,Party,Points,Penalty,A,B,C");
+    assert_eq!(lines[1], "1,alice,2300,0,480,820,900");
+    assert_eq!(
+        lines[2],
+        r#"2,"Bob, Carol and ""friends""",1200,0,450,750,0"#
+    );
+}
+
+#[test]
+pub fn client_default() {
+    fn make<T: Default>() -> T {
+        T::default()
+    }
+    let _: Client = make();
+}
+
+#[test]
+pub fn standings_solve_times() {
+    let times = standings_fixture().solve_times();
+
+    assert_eq!(times.len(), 3);
+    assert_eq!(
+        times["A"],
+        SolveTimes {
+            count: 3,
+            min: 120,
+            median: 300,
+            max: 600
+        }
+    );
+    assert_eq!(
+        times["B"],
+        SolveTimes {
+            count: 2,
+            min: 1500,
+            median: 2250,
+            max: 3000
+        }
+    );
+    assert_eq!(
+        times["C"],
+        SolveTimes {
+            count: 1,
+            min: 5400,
+            median: 5400,
+            max: 5400
+        }
+    );
+}
+
+#[test]
+pub fn standings_solve_counts() {
+    let mut standings = standings_fixture();
+    let counts = standings.solve_counts();
+    assert_eq!(counts.len(), 3);
+    assert_eq!((counts["A"], counts["B"], counts["C"]), (3, 2, 1));
+
+    for row in &mut standings.rows {
+        row.problem_results[2].points = 0.0;
+    }
+    assert_eq!(standings.solve_counts()["C"], 0);
+}
+
+#[test]
+pub fn rankings_builder_limit() {
+    let mut b = ContestRankingsBuilder::default();
+    b.limit(0, 50);
+    let query = Vec::<(&'static str, String)>::from(b);
+    assert!(query.contains(&("from", "1".to_owned())));
+    assert!(query.contains(&("count", "50".to_owned())));
+
+    let mut b = ContestRankingsBuilder::default();
+    b.limit(5, 0);
+    let query = Vec::<(&'static str, String)>::from(b);
+    assert!(query.contains(&("from", "5".to_owned())));
+    assert!(query.iter().all(|(k, _)| *k != "count"));
+}
+
+#[test]
+pub fn builders_share_limit_rules() {
+    let unset = Vec::<(&'static str, String)>::from(UserStatusBuilder::default());
+    assert!(unset.is_empty());
+    let unset = Vec::<(&'static str, String)>::from(ContestRankingsBuilder::default());
+    assert!(unset.iter().all(|(k, _)| *k != "from" && *k != "count"));
+
+    for &(from, count, ref expected) in &[
+        (0, 10, vec![("from", "1"), ("count", "10")]),
+        (3, 0, vec![("from", "3")]),
+        (0, 0, vec![("from", "1")]),
+    ] {
+        let expected: Vec<_> = expected.iter().map(|&(k, v)| (k, v.to_owned())).collect();
+        let mut b = UserStatusBuilder::default();
+        b.limit(from, count);
+        assert_eq!(Vec::<(&'static str, String)>::from(b), expected);
+        let mut b = ContestRankingsBuilder::default();
+        b.limit(from, count);
+        assert_eq!(Vec::<(&'static str, String)>::from(b)[1..], expected[..]);
+    }
+}
+
+#[tokio::test]
+pub async fn user_status_count_is_not_truncated() {
+    let ok = r#"{"status": "OK", "result": []}"#;
+    let (url, requests) = serve(vec![(200, ok), (200, ok), (200, ok)]).await;
+    let http = Client::builder()
+        .base_url(url)
+        .rate_limit(10, std::time::Duration::from_millis(1))
+        .build()
+        .unwrap();
+
+    User::status(&http, "tourist", 0, 50).await.unwrap();
+    User::status(&http, "tourist", 1, 0).await.unwrap();
+    User::status_with(&http, "tourist", |b| b).await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert!(requests[0].starts_with("GET /api/user.status?handle=tourist&from=1&count=50 "));
+    assert!(requests[1].starts_with("GET /api/user.status?handle=tourist&from=1 "));
+    assert!(requests[2].starts_with("GET /api/user.status?handle=tourist "));
+}
+
+#[test]
+pub fn contest_difficulty_stars() {
+    let mut contest = standings_fixture().contest;
+    assert_eq!(contest.difficulty_stars(), None);
+    assert_eq!(contest.difficulty_star_string(), None);
+
+    contest.difficulty = Some(3);
+    assert_eq!(contest.difficulty_stars(), Some(3));
+    assert_eq!(contest.difficulty_star_string().unwrap(), "★★★☆☆");
+
+    contest.difficulty = Some(9);
+    assert_eq!(contest.difficulty_stars(), Some(5));
+    assert_eq!(contest.difficulty_star_string().unwrap(), "★★★★★");
+}
+
+fn user_fixture(handle: &str) -> User {
+    serde_json::from_value(serde_json::json!({
+        "handle": handle,
+        "contribution": 0,
+        "lastOnlineTimeSeconds": 1600000000u64,
+        "registrationTimeSeconds": 1500000000u64,
+        "friendOfCount": 0,
+        "avatar": "//userpic.codeforces.org/no-avatar.jpg",
+        "titlePhoto": "//userpic.codeforces.org/no-title.jpg",
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+pub async fn info_partial_isolates_invalid_handles() {
+    use std::cell::Cell;
+
+    let existing = ["alice", "bob", "carol", "dave", "erin"];
+    let requests = Cell::new(0);
+    let fetch = |hs: Vec<String>| {
+        requests.set(requests.get() + 1);
+        async move {
+            match hs.iter().find(|h| !existing.contains(&h.as_str())) {
+                Some(h) => Err(Error::Codeforces(
+                    format!("handles: User with handle {} not found", h).into(),
+                )),
+                None => Ok(hs.iter().map(|h| user_fixture(h)).collect()),
+            }
+        }
+    };
+
+    let handles = [
+        "alice", "ghost1", "bob", "carol", "x", "dave", "ghost2", "erin",
+    ];
+    let (users, invalid) =
+        isolate_invalid_handles(handles.iter().map(|h| h.to_string()).collect(), fetch)
+            .await
+            .unwrap();
+
+    let users: Vec<_> = users.iter().map(|u| u.handle.as_str()).collect();
+    assert_eq!(users, ["alice", "bob", "carol", "dave", "erin"]);
+    assert_eq!(invalid, ["x", "ghost1", "ghost2"]);
+    assert!(requests.get() > 1);
+
+    // Other errors are propagated.
+    let result = isolate_invalid_handles(vec!["alice".to_owned()], |_| async {
+        Err::<Vec<User>, _>(Error::Codeforces("Call limit exceeded".into()))
+    })
+    .await;
+    assert!(result.is_err());
+}
+
+#[test]
+pub fn standings_changed_rows() {
+    let old = standings_fixture();
+    assert!(old.changed_rows(&old).is_empty());
+
+    let mut new = old.clone();
+    // dave solves B and overtakes the team.
+    new.rows[2].points = 1300.0;
+    new.rows[2].rank = 2;
+    new.rows[1].rank = 3;
+    new.rows.swap(1, 2);
+    // alice's penalty is not tracked.
+    new.rows[0].penalty = 10;
+
+    let changed: Vec<_> = new
+        .changed_rows(&old)
+        .into_iter()
+        .map(|r| r.party.name())
+        .collect();
+    assert_eq!(changed, ["dave", "Bob, Carol and \"friends\""]);
+
+    // Newly appeared parties count as changes.
+    let mut fewer = old.clone();
+    fewer.rows.pop();
+    let changed: Vec<_> = old
+        .changed_rows(&fewer)
+        .into_iter()
+        .map(|r| r.rank)
+        .collect();
+    assert_eq!(changed, [3]);
+}
+
+#[tokio::test]
+pub async fn mock_client() {
+    let user = r#"{"status": "OK", "result": [{
+        "handle": "natsukagami", "rating": 2000, "contribution": 0,
+        "lastOnlineTimeSeconds": 1600000000, "registrationTimeSeconds": 1500000000,
+        "friendOfCount": 0, "avatar": "", "titlePhoto": ""
+    }]}"#;
+    let mut responses = std::collections::HashMap::new();
+    responses.insert("user.info".to_owned(), user.to_owned());
+    responses.insert(
+        "https://codeforces.com/api/user.rating?handle=vjudge2".to_owned(),
+        r#"{"status": "OK", "result": []}"#.to_owned(),
+    );
+    let http = Client::mock(responses);
+
+    let users = User::info(&http, &["natsukagami"]).await.unwrap();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].handle, "natsukagami");
+    assert_eq!(users[0].rating, Some(2000));
+
+    assert!(User::rating(&http, "vjudge2").await.unwrap().is_empty());
+    // No response for this one.
+    assert!(User::rating(&http, "natsukagami").await.is_err());
+}
+
+fn submission_fixture() -> Submission {
+    serde_json::from_value(serde_json::json!({
+        "id": 100000,
+        "contestId": 1,
+        "creationTimeSeconds": 1600000300u64,
+        "relativeTimeSeconds": 300,
+        "problem": {"contestId": 1, "index": "A", "name": "Alpha", "type": "PROGRAMMING", "points": 100.0, "tags": ["math"]},
+        "author": {"contestId": 1, "members": [{"handle": "alice"}], "participantType": "CONTESTANT", "ghost": false},
+        "programmingLanguage": "GNU C++17",
+        "verdict": "WRONG_ANSWER",
+        "testset": "TESTS",
+        "passedTestCount": 15,
+        "timeConsumedMillis": 46,
+        "memoryConsumedBytes": 1024,
+    }))
+    .unwrap()
+}
+
+#[test]
+pub fn submission_points_awarded() {
+    let mut sub = submission_fixture();
+    assert_eq!(sub.points_awarded(20), Some(75.0));
+    assert_eq!(sub.points_awarded(10), Some(100.0));
+    assert_eq!(sub.points_awarded(0), None);
+
+    sub.verdict = Some(Verdict::Ok);
+    assert_eq!(sub.points_awarded(0), Some(100.0));
+
+    sub.problem.points = None;
+    assert_eq!(sub.points_awarded(20), None);
+
+    // Points reported by the API take precedence over the estimate.
+    sub.points = Some(37.5);
+    assert_eq!(sub.points_awarded(20), Some(37.5));
+    sub.problem.points = Some(100.0);
+    sub.verdict = Some(Verdict::Partial);
+    assert_eq!(sub.points_awarded(0), Some(37.5));
+}
+
+#[test]
+pub fn server_time_offset() {
+    // Sun, 06 Nov 1994 08:49:37 GMT is 784111777.
+    let date = "Sun, 06 Nov 1994 08:49:37 GMT";
+    assert_eq!(client::time_offset(date, 784111777).unwrap(), 0);
+    assert_eq!(client::time_offset(date, 784111770).unwrap(), 7);
+    assert_eq!(client::time_offset(date, 784111800).unwrap(), -23);
+    assert!(client::time_offset("yesterday", 784111777).is_err());
+}
+
+#[test]
+pub fn contest_kind_parsing() {
+    assert_eq!(
+        ContestKind::from("Official ICPC Contest"),
+        ContestKind::Official
+    );
+    assert_eq!(
+        ContestKind::from("Official School Contest"),
+        ContestKind::Official
+    );
+    assert_eq!(
+        ContestKind::from("Training Camp Contest"),
+        ContestKind::Training
+    );
+    assert_eq!(ContestKind::from("Training Contest"), ContestKind::Training);
+    assert_eq!(ContestKind::from("Mashup"), ContestKind::Mashup);
+    assert_eq!(
+        ContestKind::from("official personal contest"),
+        ContestKind::Official
+    );
+    assert_eq!(
+        ContestKind::from("Unofficial Contest"),
+        ContestKind::Other("Unofficial Contest".to_owned())
+    );
+    assert_eq!(
+        ContestKind::from("Opencup Contest"),
+        ContestKind::Other("Opencup Contest".to_owned())
+    );
+
+    let mut contest = standings_fixture().contest;
+    assert_eq!(contest.parsed_kind(), ContestKind::Official);
+    contest.id = 102000;
+    assert_eq!(contest.parsed_kind(), ContestKind::Gym);
+    contest.kind = Some("Training Camp Contest".to_owned());
+    assert_eq!(contest.parsed_kind(), ContestKind::Training);
+}
+
+#[tokio::test]
+pub async fn empty_results_are_ok() {
+    let empty = r#"{"status": "OK", "result": []}"#.to_owned();
+    let responses = ["user.rating", "user.status", "user.info", "contest.list"]
+        .iter()
+        .map(|m| (m.to_string(), empty.clone()))
+        .collect();
+    let http = Client::mock(responses);
+
+    // A never-rated user.
+    assert!(User::rating(&http, "neverrated").await.unwrap().is_empty());
+    // `from` past the end of the submissions.
+    assert!(User::status(&http, "natsukagami", 1_000_000, 10)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(Contest::list(&http, false).await.unwrap().is_empty());
+    let (users, invalid) = User::info_partial(&http, &["natsukagami"]).await.unwrap();
+    assert!(users.is_empty() && invalid.is_empty());
+}
+
+#[test]
+pub fn standings_iter() {
+    let mut standings = standings_fixture();
+    let rows: Vec<_> = (&standings).into_iter().collect();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[0].0.party.name(), "alice");
+    assert_eq!(rows[0].1["B"].points, 820.0);
+    assert_eq!(rows[1].1["C"].rejected_attempt_count, 3);
+    assert_eq!(rows[2].1.len(), 3);
+
+    // Misaligned rows are handled defensively.
+    standings.rows[0].problem_results.pop();
+    let extra = standings.rows[1].problem_results[0].clone();
+    standings.rows[1].problem_results.push(extra);
+    for (row, results) in &standings {
+        assert_eq!(results.len(), row.problem_results.len().min(3));
+    }
+    assert!(!(&standings).into_iter().next().unwrap().1.contains_key("C"));
+}
+
+#[test]
+pub fn id_newtypes() {
+    let id: ContestId = 566.into();
+    assert_eq!(id, ContestId(566));
+    assert_eq!(u64::from(id), 566);
+    assert_eq!(id.to_string(), "566");
+    assert_eq!(serde_json::from_str::<ContestId>("566").unwrap(), id);
+
+    let index: ProblemIndex = "B1".into();
+    assert_eq!(index, ProblemIndex::from("B1".to_owned()));
+    assert_eq!(index.as_ref(), "B1");
+    assert_eq!(index.to_string(), "B1");
+    assert_eq!(
+        serde_json::from_str::<ProblemIndex>(r#""B1""#).unwrap(),
+        index
+    );
+}
+
+#[tokio::test]
+pub async fn latest_rating_change() {
+    let mut responses = std::collections::HashMap::new();
+    responses.insert(
+        "https://codeforces.com/api/user.rating?handle=unrated".to_owned(),
+        r#"{"status": "OK", "result": []}"#.to_owned(),
+    );
+    responses.insert(
+        "https://codeforces.com/api/user.rating?handle=rated".to_owned(),
+        r#"{"status": "OK", "result": [
+            {"contestId": 1, "contestName": "Round 1", "handle": "rated", "rank": 10,
+             "ratingUpdateTimeSeconds": 100, "oldRating": 0, "newRating": 1500},
+            {"contestId": 2, "contestName": "Round 2", "handle": "rated", "rank": 5,
+             "ratingUpdateTimeSeconds": 200, "oldRating": 1500, "newRating": 1620}
+        ]}"#
+        .to_owned(),
+    );
+    let http = Client::mock(responses);
+
+    assert!(User::latest_rating_change(&http, "unrated")
+        .await
+        .unwrap()
+        .is_none());
+    let latest = User::latest_rating_change(&http, "rated")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(latest.contest_id, 2);
+    assert_eq!(latest.new_rating, 1620);
+}
+
+#[test]
+pub fn problem_points_integer_or_float() {
+    let parse = |points: &str| -> Problem {
+        serde_json::from_str(&format!(
+            r#"{{"contestId": 1, "index": "A", "name": "Alpha", "type": "PROGRAMMING", {} "tags": []}}"#,
+            points
+        ))
+        .unwrap()
+    };
+    assert_eq!(parse(r#""points": 500,"#).points, Some(500.0));
+    assert_eq!(parse(r#""points": 1.5,"#).points, Some(1.5));
+    assert_eq!(parse(r#""points": null,"#).points, None);
+    assert_eq!(parse("").points, None);
+}
+
+#[test]
+pub fn compact_standings() {
+    use compact::*;
+    use std::mem::size_of;
+
+    // A large contest with 20000 rows of 2-member teams and 8 problems.
+    let result = r#"{"points": 1.0, "rejectedAttemptCount": 0, "type": "FINAL"}"#;
+    let results = [result; 8].join(",");
+    let rows: Vec<_> = (0..20000)
+        .map(|i| {
+            format!(
+                r#"{{"party": {{"members": [{{"handle": "user{i}a"}}, {{"handle": "user{i}b"}}],
+                    "participantType": "CONTESTANT", "teamName": "team {i}", "ghost": false}},
+                    "rank": {i}, "points": 8.0, "penalty": 100, "successfulHackCount": 0,
+                    "unsuccessfulHackCount": 0, "problemResults": [{results}]}}"#,
+                i = i,
+                results = results
+            )
+        })
+        .collect();
+    let json = format!("[{}]", rows.join(","));
+
+    let owned: Vec<RanklistRow> = serde_json::from_str(&json).unwrap();
+    let compact: Box<[CompactRanklistRow]> = serde_json::from_str(&json).unwrap();
+
+    let owned_bytes: usize = owned
+        .iter()
+        .map(|r| {
+            size_of::<RanklistRow>()
+                + r.party.members.capacity() * size_of::<TeamMember>()
+                + r.party
+                    .members
+                    .iter()
+                    .map(|m| m.handle.capacity())
+                    .sum::<usize>()
+                + r.party.team_name.as_ref().map_or(0, |n| n.capacity())
+                + r.problem_results.capacity() * size_of::<ProblemResult>()
+        })
+        .sum();
+    let compact_bytes: usize = compact
+        .iter()
+        .map(|r| {
+            size_of::<CompactRanklistRow>()
+                + r.party.members.len() * size_of::<CompactTeamMember>()
+                + r.party
+                    .members
+                    .iter()
+                    .map(|m| m.handle.len())
+                    .sum::<usize>()
+                + r.party.team_name.as_ref().map_or(0, |n| n.len())
+                + r.problem_results.len() * size_of::<ProblemResult>()
+        })
+        .sum();
+    assert!(compact_bytes < owned_bytes);
+
+    // Converting back gives the same rows.
+    let standings: Vec<RanklistRow> = compact.into_vec().into_iter().map(Into::into).collect();
+    assert_eq!(standings[42].party.name(), owned[42].party.name());
+    assert_eq!(standings[42].problem_results, owned[42].problem_results);
+}
+
+#[test]
+pub fn submission_systest_filter() {
+    use SubmissionTestSet::*;
+    let subs: Vec<_> = [
+        Samples, Pretests, Tests, Challenges, TestSet1, TestSet10, Tests,
+    ]
+    .iter()
+    .enumerate()
+    .map(|(i, &t)| Submission {
+        id: i as u64,
+        test_set: t,
+        ..submission_fixture()
+    })
+    .collect();
+
+    assert_eq!(
+        subs.iter().map(Submission::is_systest).collect::<Vec<_>>(),
+        [false, false, true, false, false, false, true]
+    );
+    let ids: Vec<_> = systest_submissions(&subs).iter().map(|s| s.id).collect();
+    assert_eq!(ids, [2, 6]);
+}
+
+/// Serves the given `(status, body)` responses in order over HTTP on a local
+/// port, one per connection.
+///
+/// Returns the base URL of the server, and the received requests (request line
+/// and headers).
+async fn serve(
+    responses: Vec<(u16, &'static str)>,
+) -> (String, std::sync::Arc<std::sync::Mutex<Vec<String>>>) {
+    let responses = responses
+        .into_iter()
+        .map(|(status, body)| {
+            format!(
+                "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+        })
+        .collect();
+    serve_raw(responses).await
+}
+
+/// Like [`serve`], but with the full raw HTTP responses.
+async fn serve_raw<R: Into<Vec<u8>> + Send + 'static>(
+    responses: Vec<R>,
+) -> (String, std::sync::Arc<std::sync::Mutex<Vec<String>>>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/api", listener.local_addr().unwrap());
+    let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let received = requests.clone();
+    tokio::spawn(async move {
+        for response in responses {
+            let (mut conn, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = conn.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            received
+                .lock()
+                .unwrap()
+                .push(String::from_utf8_lossy(&request).into_owned());
+            conn.write_all(&response.into()).await.unwrap();
+        }
+    });
+    (url, requests)
+}
+
+#[tokio::test]
+pub async fn custom_base_url() {
+    let (url, requests) = serve(vec![(200, r#"{"status": "OK", "result": []}"#)]).await;
+    let http = Client::with_base_url(url.clone() + "/");
+    assert_eq!(http.base_url(), url);
+
+    assert!(User::rating(&http, "natsukagami").await.unwrap().is_empty());
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].starts_with("GET /api/user.rating?handle=natsukagami HTTP/1.1"));
+}
+
+fn rating_history_fixture() -> Vec<RatingChange> {
+    [
+        (100, 0, 1500),
+        (200, 1500, 1620),
+        (300, 1620, 1580),
+        (400, 1580, 1700),
+    ]
+    .iter()
+    .enumerate()
+    .map(|(i, &(time, old, new))| RatingChange {
+        contest_id: i as u64 + 1,
+        contest_name: format!("Round {}", i + 1),
+        handle: "alice".to_owned(),
+        rank: 10,
+        rating_update_time_seconds: time,
+        old_rating: old,
+        new_rating: new,
+    })
+    .collect()
+}
+
+#[test]
+pub fn rating_range_queries() {
+    let history = rating_history_fixture();
+    let ids = |r: &[RatingChange]| r.iter().map(|c| c.contest_id).collect::<Vec<_>>();
+
+    assert_eq!(ids(rating_in_range(&history, 200, 300)), [2, 3]);
+    assert_eq!(ids(rating_in_range(&history, 150, 350)), [2, 3]);
+    assert_eq!(ids(rating_in_range(&history, 0, 1000)), [1, 2, 3, 4]);
+    assert_eq!(ids(rating_in_range(&history, 400, 400)), [4]);
+    assert!(rating_in_range(&history, 201, 299).is_empty());
+    assert!(rating_in_range(&history, 300, 200).is_empty());
+    assert!(rating_in_range(&[], 0, 1000).is_empty());
+}
+
+#[test]
+pub fn virtual_party_start() {
+    let mut row = standings_fixture().rows.remove(0);
+    row.party.start_time_seconds = Some(1600100000);
+    assert_eq!(row.virtual_start(), None);
+
+    row.party.participant_type = ParticipantType::Virtual;
+    assert_eq!(row.virtual_start(), Some(1600100000));
+    let solved_at =
+        row.virtual_start().unwrap() + row.problem_results[0].best_submission_time_seconds.unwrap();
+    assert_eq!(solved_at, 1600100300);
+}
+
+#[test]
+pub fn standings_projection() {
+    let standings = standings_fixture();
+    let projected = standings.project(&["C", "A", "Z"]);
+
+    let indices: Vec<_> = projected
+        .problems
+        .iter()
+        .map(|p| p.index.as_str())
+        .collect();
+    assert_eq!(indices, ["A", "C"]);
+    let typed = standings.project(&[ProblemIndex::from("A"), "C".into()]);
+    assert_eq!(typed.problems.len(), 2);
+    assert_eq!(projected.rows.len(), standings.rows.len());
+    for (old, new) in standings.rows.iter().zip(&projected.rows) {
+        assert_eq!(new.rank, old.rank);
+        assert_eq!(
+            new.problem_results,
+            [
+                old.problem_results[0].clone(),
+                old.problem_results[2].clone()
+            ]
+        );
+    }
+    assert!(standings
+        .project::<&str>(&[])
+        .rows
+        .iter()
+        .all(|r| r.problem_results.is_empty()));
+}
+
+#[tokio::test]
+pub async fn rate_limit_is_fifo() {
+    use client::rate_limit::Ratelimit;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    let limit = Arc::new(Ratelimit::new(
+        (),
+        1,
+        Duration::from_millis(10),
+        RateLimitMode::Burst,
+    ));
+    let order = Arc::new(Mutex::new(Vec::new()));
+
+    let held = Ratelimit::borrow(&limit).await.unwrap();
+    let mut tasks = Vec::new();
+    for i in 0..8 {
+        let limit = limit.clone();
+        let order = order.clone();
+        tasks.push(tokio::spawn(async move {
+            let _permit = Ratelimit::borrow(&limit).await.unwrap();
+            order.lock().unwrap().push(i);
+        }));
+        // Let the task start waiting before spawning the next one.
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+    drop(held);
+    for task in tasks {
+        task.await.unwrap();
+    }
+    assert_eq!(*order.lock().unwrap(), (0..8).collect::<Vec<_>>());
+}
+
+#[test]
+pub fn submission_with_minimal_problem() {
+    let sub: Submission = serde_json::from_value(serde_json::json!({
+        "id": 100001,
+        "creationTimeSeconds": 1600000300u64,
+        "problem": {"index": "A"},
+        "author": {"members": [{"handle": "alice"}], "participantType": "PRACTICE", "ghost": false},
+        "programmingLanguage": "GNU C++17",
+        "verdict": "OK",
+        "testset": "TESTS",
+        "passedTestCount": 20,
+        "timeConsumedMillis": 46,
+        "memoryConsumedBytes": 1024,
+    }))
+    .unwrap();
+    assert_eq!(sub.problem.index, "A");
+    assert_eq!(sub.problem.contest_id, None);
+    assert_eq!(sub.problem.name, "");
+    assert_eq!(sub.problem.problem_type, ProblemType::Programming);
+    assert!(sub.problem.tags.is_empty());
+}
+
+#[tokio::test]
+pub async fn client_shutdown() {
+    use client::rate_limit::Ratelimit;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let limit = Arc::new(Ratelimit::new(
+        (),
+        1,
+        Duration::from_millis(10),
+        RateLimitMode::Burst,
+    ));
+    let in_flight = Ratelimit::borrow(&limit).await.unwrap();
+    let waiting = tokio::spawn({
+        let limit = limit.clone();
+        async move { Ratelimit::borrow(&limit).await.is_some() }
+    });
+    tokio::time::sleep(Duration::from_millis(5)).await;
+
+    let shutdown = tokio::spawn({
+        let limit = limit.clone();
+        async move { limit.close().await }
+    });
+    // Waiting and new borrows fail fast, while the in-flight one holds on.
+    assert!(!waiting.await.unwrap());
+    assert!(Ratelimit::borrow(&limit).await.is_none());
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(!shutdown.is_finished());
+
+    drop(in_flight);
+    tokio::time::timeout(Duration::from_secs(1), shutdown)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let (url, _) = serve(vec![]).await;
+    let http = Client::with_base_url(url);
+    http.shutdown().await;
+    assert!(matches!(
+        User::rating(&http, "natsukagami").await,
+        Err(Error::Shutdown)
+    ));
+}
+
+#[test]
+pub fn rated_list_percentiles() {
+    let users: Vec<_> = [3000, 2400, 2400, 1900, 1500, 1200, 1200, 800]
+        .iter()
+        .map(|&r| User {
+            rating: Some(r),
+            ..user_fixture("someone")
+        })
+        .collect();
+
+    assert_eq!(rated_list_percentile(&users, 3500), 100.0);
+    assert_eq!(rated_list_percentile(&users, 3000), 87.5);
+    assert_eq!(rated_list_percentile(&users, 2400), 62.5);
+    assert_eq!(rated_list_percentile(&users, 2000), 62.5);
+    assert_eq!(rated_list_percentile(&users, 800), 0.0);
+    assert_eq!(rated_list_percentile(&users, 0), 0.0);
+    assert_eq!(rated_list_percentile(&[], 1500), 0.0);
+
+    assert_eq!(rated_list_rank(&users, 3500), 1);
+    assert_eq!(rated_list_rank(&users, 2400), 2);
+    assert_eq!(rated_list_rank(&users, 2000), 4);
+    assert_eq!(rated_list_rank(&users, 0), 9);
+}
+
+fn problem_json(contest_id: u64, index: &str, rating: u64, tags: &[&str]) -> serde_json::Value {
+    serde_json::json!({
+        "contestId": contest_id, "index": index, "name": format!("Problem {}{}", contest_id, index),
+        "type": "PROGRAMMING", "rating": rating, "tags": tags,
+    })
+}
+
+fn problemset_response(problems: &[serde_json::Value]) -> String {
+    let stats: Vec<_> = problems
+        .iter()
+        .map(|p| serde_json::json!({"contestId": p["contestId"], "index": p["index"], "solvedCount": 100}))
+        .collect();
+    serde_json::json!({"status": "OK", "result": {"problems": problems, "problemStatistics": stats}})
+        .to_string()
+}
+
+#[tokio::test]
+pub async fn problems_any_tag() {
+    let both = problem_json(1, "A", 1200, &["dp", "greedy"]);
+    let dp = problem_json(2, "B", 1600, &["dp"]);
+    let greedy = problem_json(3, "C", 800, &["greedy"]);
+
+    let url = "https://codeforces.com/api/problemset.problems?tags=";
+    let responses = vec![
+        (
+            url.to_owned() + "dp",
+            problemset_response(&[both.clone(), dp]),
+        ),
+        (
+            url.to_owned() + "greedy",
+            problemset_response(&[greedy, both.clone()]),
+        ),
+        (url.to_owned() + "dp%3Bgreedy", problemset_response(&[both])),
+    ];
+    let http = Client::mock(responses.into_iter().collect());
+
+    let (and, stats) = Problemset::problems(&http, &["dp", "greedy"])
+        .await
+        .unwrap();
+    assert_eq!(and.len(), 1);
+    assert_eq!(stats[0].solved_count, 100);
+
+    let or = Problemset::problems_any_tag(&http, &["dp", "greedy"])
+        .await
+        .unwrap();
+    let ids: Vec<_> = or.iter().map(Problem::identity).collect();
+    assert_eq!(ids, [(Some(1), "A"), (Some(2), "B"), (Some(3), "C")]);
+    assert!(and.iter().all(|p| ids.contains(&p.identity())));
+}
+
+#[tokio::test]
+pub async fn call_limit_retry_reports_wait() {
+    let (url, requests) = serve(vec![
+        (
+            503,
+            r#"{"status": "FAILED", "comment": "Call limit exceeded"}"#,
+        ),
+        (200, r#"{"status": "OK", "result": []}"#),
+    ])
+    .await;
+    let http = Client::with_base_url(url);
+    assert_eq!(http.total_wait(), std::time::Duration::from_secs(0));
+
+    assert!(User::rating(&http, "natsukagami").await.unwrap().is_empty());
+    assert_eq!(requests.lock().unwrap().len(), 2);
+    // The retry waited for the next permit.
+    assert!(http.total_wait() >= std::time::Duration::from_secs(1));
+}
+
+#[test]
+pub fn standings_teams() {
+    let mut standings = standings_fixture();
+    let mut other_room = standings.rows[1].clone();
+    other_room.party.room = Some(2);
+    other_room.party.participant_type = ParticipantType::OutOfCompetition;
+    standings.rows.push(other_room);
+    standings.rows[0].party.team_id = Some(9);
+
+    let teams = standings.teams();
+    assert_eq!(teams.len(), 2);
+    assert_eq!(teams[&7].len(), 2);
+    assert!(teams[&7].iter().all(|r| r.party.team_id == Some(7)));
+    assert_eq!(teams[&9][0].party.name(), "alice");
+}
+
+#[tokio::test]
+pub async fn client_builder_connection_settings() {
+    let ok = r#"{"status": "OK", "result": []}"#;
+    let (url, requests) = serve(vec![(200, ok)]).await;
+    let http = Client::builder()
+        .base_url(url)
+        .pool_max_idle_per_host(1)
+        .tcp_keepalive(Some(std::time::Duration::from_secs(60)))
+        .build()
+        .unwrap();
+    assert!(User::rating(&http, "natsukagami").await.unwrap().is_empty());
+    assert_eq!(requests.lock().unwrap().len(), 1);
+
+    // The test server only speaks HTTP/1.1.
+    let (url, requests) = serve(vec![(200, ok)]).await;
+    let http = Client::builder()
+        .base_url(url)
+        .http2_prior_knowledge(true)
+        .build()
+        .unwrap();
+    assert!(matches!(
+        User::rating(&http, "natsukagami").await,
+        Err(Error::Http(_))
+    ));
+    assert!(requests.lock().unwrap()[0].starts_with("PRI * HTTP/2.0"));
+}
+
+#[tokio::test]
+pub async fn client_builder_rejects_empty_rate_limit() {
+    let second = std::time::Duration::from_secs(1);
+    assert!(matches!(
+        Client::builder().rate_limit(0, second).build(),
+        Err(Error::Local(_))
+    ));
+    assert!(Client::builder().rate_limit(1, second).build().is_ok());
+}
+
+#[tokio::test]
+pub async fn client_builder_rejects_empty_concurrency_limit() {
+    assert!(matches!(
+        Client::builder().concurrency_limit(0).build(),
+        Err(Error::Local(_))
+    ));
+    assert!(Client::builder().concurrency_limit(1).build().is_ok());
+}
+
+#[test]
+#[should_panic(expected = "concurrency limit")]
+pub fn empty_concurrency_limit_panics() {
+    Client::with_concurrency_limit(0);
+}
+
+#[cfg(feature = "middleware")]
+#[tokio::test]
+pub async fn middleware_sees_api_calls() {
+    use reqwest_middleware::{Middleware, Next};
+    use std::sync::{Arc, Mutex};
+
+    /// Logs the URL of each request, failing them if `fail` is set.
+    struct Log {
+        urls: Arc<Mutex<Vec<String>>>,
+        fail: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl Middleware for Log {
+        async fn handle(
+            &self,
+            req: reqwest::Request,
+            extensions: &mut task_local_extensions::Extensions,
+            next: Next<'_>,
+        ) -> reqwest_middleware::Result<reqwest::Response> {
+            self.urls.lock().unwrap().push(req.url().to_string());
+            if self.fail {
+                return Err(reqwest_middleware::Error::Middleware(anyhow::anyhow!(
+                    "rejected"
+                )));
+            }
+            next.run(req, extensions).await
+        }
+    }
+
+    let ok = r#"{"status": "OK", "result": []}"#;
+    let (url, requests) = serve(vec![(200, ok), (200, ok)]).await;
+    let urls = Arc::new(Mutex::new(Vec::new()));
+    let middleware = |fail| {
+        reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+            .with(Log {
+                urls: urls.clone(),
+                fail,
+            })
+            .build()
+    };
+    let http = Client::builder()
+        .base_url(url.clone())
+        .rate_limit(1, std::time::Duration::from_millis(200))
+        .middleware(middleware(false))
+        .build()
+        .unwrap();
+    let start = std::time::Instant::now();
+    assert!(User::rating(&http, "alice").await.unwrap().is_empty());
+    assert!(User::rating(&http, "bob").await.unwrap().is_empty());
+    // Still rate-limited.
+    assert!(start.elapsed() >= std::time::Duration::from_millis(200));
+    assert_eq!(
+        *urls.lock().unwrap(),
+        [
+            format!("{}/user.rating?handle=alice", url),
+            format!("{}/user.rating?handle=bob", url),
+        ]
+    );
+    assert_eq!(requests.lock().unwrap().len(), 2);
+
+    let http = Client::builder()
+        .base_url(url)
+        .middleware(middleware(true))
+        .build()
+        .unwrap();
+    assert!(matches!(
+        User::rating(&http, "carol").await,
+        Err(Error::Middleware(_))
+    ));
+    assert_eq!(http.metrics().middleware_errors, 1);
+    assert_eq!(requests.lock().unwrap().len(), 2);
+}
+
+#[test]
+pub fn contests_sorted_by_start() {
+    let base = standings_fixture().contest;
+    let mut contests: Vec<_> = [
+        (1, Some(300)),
+        (2, None),
+        (3, Some(100)),
+        (4, None),
+        (5, Some(200)),
+    ]
+    .iter()
+    .map(|&(id, start)| Contest {
+        id,
+        start_time_seconds: start,
+        ..base.clone()
+    })
+    .collect();
+
+    sort_by_start(&mut contests);
+    let ids: Vec<_> = contests.iter().map(|c| c.id).collect();
+    assert_eq!(ids, [3, 5, 1, 2, 4]);
+}
+
+#[tokio::test]
+pub async fn api_errors_carry_the_call() {
+    let mut responses = std::collections::HashMap::new();
+    responses.insert(
+        "user.info".to_owned(),
+        r#"{"status":"FAILED","comment":"handles: User with handle nobody not found"}"#.to_owned(),
+    );
+    let http = Client::mock(responses);
+
+    match User::info(&http, &["nobody"]).await {
+        Err(Error::Codeforces(e)) => {
+            assert_eq!(e.comment, "handles: User with handle nobody not found");
+            assert_eq!(e.method.as_deref(), Some("user.info"));
+            assert_eq!(e.params, [("handles".to_owned(), "nobody".to_owned())]);
+            assert_eq!(
+                Error::Codeforces(e).to_string(),
+                "Codeforces: handles: User with handle nobody not found (in user.info)"
+            );
+        }
+        v => panic!("expected a codeforces error, got {:?}", v),
+    }
+
+    let e = ApiError::from("Incorrect signature").with_call(
+        "user.friends",
+        &[
+            ("apiKey", "key".to_owned()),
+            ("onlyOnline", "true".to_owned()),
+        ],
+    );
+    assert_eq!(
+        e.params,
+        [
+            ("apiKey".to_owned(), "<redacted>".to_owned()),
+            ("onlyOnline".to_owned(), "true".to_owned())
+        ]
+    );
+}
+
+#[test]
+pub fn submissions_sort_by_creation() {
+    let mut subs: Vec<_> = [(3, 300), (1, 100), (4, 300), (2, 200)]
+        .iter()
+        .map(|&(id, time)| Submission {
+            id,
+            creation_time_seconds: time,
+            ..submission_fixture()
+        })
+        .collect();
+    sort_by_creation(&mut subs);
+    let ids: Vec<_> = subs.iter().map(|s| s.id).collect();
+    assert_eq!(ids, [1, 2, 3, 4]);
+    assert_eq!(
+        subs[3].cmp_by_creation(&subs[2]),
+        std::cmp::Ordering::Greater
+    );
+}
+
+#[test]
+pub fn tag_solve_counts_dedups_problems() {
+    let sub = |id, index: &str, tags: &[&str], verdict| {
+        let mut s = submission_fixture();
+        s.id = id;
+        s.problem.index = index.to_owned();
+        s.problem.tags = tags.iter().map(|t| t.to_string()).collect();
+        s.verdict = Some(verdict);
+        s
+    };
+    let subs = [
+        sub(1, "A", &["math", "greedy"], Verdict::Ok),
+        // Accepted again, still counted once.
+        sub(2, "A", &["math", "greedy"], Verdict::Ok),
+        sub(3, "B", &["math", "dp"], Verdict::Ok),
+        // Not accepted.
+        sub(4, "C", &["graphs"], Verdict::WrongAnswer),
+        sub(5, "D", &[], Verdict::Ok),
+    ];
+
+    let counts = tag_solve_counts(&subs);
+    assert_eq!(counts.len(), 3);
+    assert_eq!(counts["math"], 2);
+    assert_eq!(counts["greedy"], 1);
+    assert_eq!(counts["dp"], 1);
+    assert!(!counts.contains_key("graphs"));
+}
+
+#[tokio::test]
+pub async fn concurrency_limit_caps_requests_in_flight() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/api", listener.local_addr().unwrap());
+    let active = Arc::new(AtomicUsize::new(0));
+    let max_active = Arc::new(AtomicUsize::new(0));
+    let (a, m) = (active.clone(), max_active.clone());
+    tokio::spawn(async move {
+        loop {
+            let (mut conn, _) = listener.accept().await.unwrap();
+            let (active, max_active) = (a.clone(), m.clone());
+            tokio::spawn(async move {
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = conn.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        return;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                max_active.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                active.fetch_sub(1, Ordering::SeqCst);
+                let body = r#"{"status": "OK", "result": []}"#;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                conn.write_all(response.as_bytes()).await.unwrap();
+            });
+        }
+    });
+
+    let http = Client::builder()
+        .base_url(url)
+        .rate_limit(8, std::time::Duration::from_millis(1))
+        .concurrency_limit(2)
+        .build()
+        .unwrap();
+    let requests = (0..8).map(|_| User::rating(&http, "natsukagami"));
+    for result in futures_util::future::join_all(requests).await {
+        assert!(result.unwrap().is_empty());
+    }
+    assert_eq!(max_active.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+pub fn user_activity_summary() {
+    let sub = |id, index: &str, time, language: &str, verdict| {
+        let mut s = submission_fixture();
+        s.id = id;
+        s.problem.index = index.to_owned();
+        s.creation_time_seconds = time;
+        s.programming_language = language.to_owned();
+        s.verdict = Some(verdict);
+        s
+    };
+    // Newest first, as returned by `user.status`.
+    let subs = vec![
+        sub(5, "B", 500, "Rust 2021", Verdict::Ok),
+        sub(4, "A", 400, "GNU C++17", Verdict::Ok),
+        sub(3, "A", 300, "GNU C++17", Verdict::Ok),
+        sub(2, "C", 200, "Python 3", Verdict::TimeLimitExceeded),
+        sub(1, "A", 100, "GNU C++17", Verdict::WrongAnswer),
+    ];
+
+    let activity = UserActivity::from(subs);
+    assert_eq!(activity.submissions, 5);
+    assert_eq!(activity.accepted, 3);
+    assert_eq!(activity.solved, 2);
+    assert_eq!(activity.first_submission_seconds, Some(100));
+    assert_eq!(activity.last_submission_seconds, Some(500));
+    assert_eq!(activity.languages.len(), 3);
+    assert_eq!(activity.languages["GNU C++17"], 3);
+    assert_eq!(activity.languages["Rust 2021"], 1);
+    assert_eq!(activity.languages["Python 3"], 1);
+
+    assert_eq!(UserActivity::from(Vec::new()), UserActivity::default());
+}
+
+/// The seed corpus of the `decode` fuzz target.
+const DECODE_CORPUS: &[&str] = &[
+    include_str!("../fuzz/corpus/decode/user.status.json"),
+    include_str!("../fuzz/corpus/decode/contest.json"),
+    include_str!("../fuzz/corpus/decode/failed.json"),
+    include_str!("../fuzz/corpus/decode/empty.json"),
+];
+
+#[test]
+pub fn decode_corpus() {
+    let subs: Vec<Submission> = decode_response(DECODE_CORPUS[0].as_bytes()).unwrap();
+    assert_eq!(subs.len(), 2);
+    let contest: Contest = decode_response(DECODE_CORPUS[1].as_bytes()).unwrap();
+    assert_eq!(contest.id, 1418);
+    assert!(matches!(
+        decode_response::<Contest>(DECODE_CORPUS[2].as_bytes()),
+        Err(Error::Codeforces(_))
+    ));
+    assert!(
+        decode_response::<Vec<Submission>>(DECODE_CORPUS[3].as_bytes())
+            .unwrap()
+            .is_empty()
+    );
+}
+
+/// A cheap stand-in for the fuzz target: truncated and corrupted responses
+/// may fail to decode, but never panic.
+#[test]
+pub fn decode_mutated_corpus_never_panics() {
+    fn decode(data: &[u8]) {
+        let _ = decode_response::<Vec<Submission>>(data);
+        let _ = decode_response::<Contest>(data);
+        let _ = serde_json::from_slice::<Standings>(data);
+    }
+    for seed in DECODE_CORPUS {
+        let seed = seed.as_bytes();
+        for len in 0..seed.len() {
+            decode(&seed[..len]);
+        }
+        for i in 0..seed.len() {
+            for &b in b"\"{}[]-0e\xff" {
+                let mut data = seed.to_vec();
+                data[i] = b;
+                decode(&data);
+            }
+        }
+    }
+}
+
+const FINISHED_STANDINGS: &str = r#"{"status": "OK", "result": {
+    "contest": {"id": 1, "name": "Test Round", "type": "CF", "phase": "FINISHED", "frozen": false, "durationSeconds": 7200},
+    "problems": [],
+    "rows": []
+}}"#;
+
+const CODING_STANDINGS: &str = r#"{"status": "OK", "result": {
+    "contest": {"id": 2, "name": "Live Round", "type": "CF", "phase": "CODING", "frozen": false, "durationSeconds": 7200},
+    "problems": [],
+    "rows": []
+}}"#;
+
+#[tokio::test]
+pub async fn finished_standings_are_cached() {
+    let dir = std::env::temp_dir().join(format!("codeforces-standings-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let (url, requests) = serve(vec![
+        (200, FINISHED_STANDINGS),
+        (200, CODING_STANDINGS),
+        (200, CODING_STANDINGS),
+    ])
+    .await;
+    let http = Client::builder()
+        .base_url(url)
+        .rate_limit(10, std::time::Duration::from_millis(1))
+        .standings_cache_dir(&dir)
+        .build()
+        .unwrap();
+
+    for _ in 0..2 {
+        let (contest, _, _) = Contest::standings(&http, 1, |b| b).await.unwrap();
+        assert_eq!(contest.phase, ContestPhase::Finished);
+    }
+    assert_eq!(requests.lock().unwrap().len(), 1);
+
+    for _ in 0..2 {
+        let (contest, _, _) = Contest::standings(&http, 2, |b| b).await.unwrap();
+        assert_eq!(contest.phase, ContestPhase::Coding);
+    }
+    assert_eq!(requests.lock().unwrap().len(), 3);
+
+    // Another client reads the finished standings back from the directory.
+    let offline = Client::builder()
+        .base_url("http://127.0.0.1:1/api")
+        .standings_cache_dir(&dir)
+        .build()
+        .unwrap();
+    let (contest, _, _) = Contest::standings(&offline, 1, |b| b).await.unwrap();
+    assert_eq!(contest.id, 1);
+    assert!(Contest::standings(&offline, 2, |b| b).await.is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+pub async fn cached_standings_files_do_not_collide() {
+    let dir =
+        std::env::temp_dir().join(format!("codeforces-standings-keys-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let (url, _) = serve(vec![(200, FINISHED_STANDINGS)]).await;
+    let http = Client::builder()
+        .base_url(url)
+        .standings_cache_dir(&dir)
+        .build()
+        .unwrap();
+    let handles = |h: &str| vec![h.to_owned()];
+    Contest::standings(&http, 1, |b| b.handles(handles("a.b")))
+        .await
+        .unwrap();
+
+    // Only the exact same query is read back from the directory.
+    let offline = Client::builder()
+        .base_url("http://127.0.0.1:1/api")
+        .standings_cache_dir(&dir)
+        .build()
+        .unwrap();
+    assert!(
+        Contest::standings(&offline, 1, |b| b.handles(handles("a.b")))
+            .await
+            .is_ok()
+    );
+    for other in &["a_b", "a-b", "a%2Eb"] {
+        let result = Contest::standings(&offline, 1, |b| b.handles(handles(other))).await;
+        assert!(result.is_err(), "{}", other);
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+pub async fn cached_standings_are_kept_per_language() {
+    let dir =
+        std::env::temp_dir().join(format!("codeforces-standings-lang-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let (url, _) = serve(vec![(200, FINISHED_STANDINGS)]).await;
+    let russian = Client::builder()
+        .base_url(url)
+        .lang("ru")
+        .standings_cache_dir(&dir)
+        .build()
+        .unwrap();
+    Contest::standings(&russian, 1, |b| b).await.unwrap();
+
+    let offline = |lang: Option<&str>| {
+        let b = Client::builder()
+            .base_url("http://127.0.0.1:1/api")
+            .standings_cache_dir(&dir);
+        match lang {
+            Some(lang) => b.lang(lang),
+            None => b,
+        }
+        .build()
+        .unwrap()
+    };
+    assert!(Contest::standings(&offline(Some("ru")), 1, |b| b)
+        .await
+        .is_ok());
+    assert!(Contest::standings(&offline(None), 1, |b| b).await.is_err());
+    assert!(Contest::standings(&offline(Some("en")), 1, |b| b)
+        .await
+        .is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+pub fn user_photo_urls_are_absolute() {
+    let mut user = user_fixture("alice");
+    assert_eq!(
+        user.avatar_url(),
+        "https://userpic.codeforces.org/no-avatar.jpg"
+    );
+    assert_eq!(
+        user.title_photo_url(),
+        "https://userpic.codeforces.org/no-title.jpg"
+    );
+
+    user.avatar = "https://userpic.codeforces.org/1/avatar/a.jpg".to_owned();
+    user.title_photo = "http://example.com/title.jpg".to_owned();
+    assert_eq!(
+        user.avatar_url(),
+        "https://userpic.codeforces.org/1/avatar/a.jpg"
+    );
+    assert_eq!(user.title_photo_url(), "http://example.com/title.jpg");
+}
+
+#[test]
+pub fn difficulty_histogram_buckets() {
+    let mut problems: Vec<Problem> = [800, 899, 900, 1550, 3500, 3600, 500]
+        .iter()
+        .map(|&r| serde_json::from_value(problem_json(1, "A", r, &[])).unwrap())
+        .collect();
+    problems[0].rating = None;
+
+    let histogram = difficulty_histogram(&problems);
+    assert_eq!(histogram.unrated, 1);
+    assert_eq!(histogram.buckets.len(), 28);
+    assert_eq!(histogram.buckets[&800], 2); // 899 and 500
+    assert_eq!(histogram.buckets[&900], 1);
+    assert_eq!(histogram.buckets[&1500], 1);
+    assert_eq!(histogram.buckets[&1600], 0);
+    assert_eq!(histogram.buckets[&3500], 2); // 3500 and 3600
+    assert_eq!(histogram.buckets.values().sum::<usize>(), 6);
+}
+
+#[tokio::test]
+pub async fn rate_limit_modes_start_differently() {
+    use client::rate_limit::Ratelimit;
+    use std::time::{Duration, Instant};
+
+    async fn time_to_borrow(limit: &Ratelimit<()>, times: usize) -> Duration {
+        let start = Instant::now();
+        for _ in 0..times {
+            drop(limit.borrow().await.unwrap());
+        }
+        start.elapsed()
+    }
+
+    let wait = Duration::from_millis(400);
+    let burst = Ratelimit::new((), 4, wait, RateLimitMode::Burst);
+    assert!(time_to_borrow(&burst, 4).await < Duration::from_millis(100));
+
+    let smooth = Ratelimit::new((), 4, wait, RateLimitMode::Smooth);
+    // The first one is handed out at once, then one every 100ms.
+    assert!(time_to_borrow(&smooth, 1).await < Duration::from_millis(50));
+    let elapsed = time_to_borrow(&smooth, 3).await;
+    assert!(elapsed >= Duration::from_millis(290), "{:?}", elapsed);
+    assert!(elapsed < wait, "{:?}", elapsed);
+}
+
+#[tokio::test]
+pub async fn errors_convert_to_io_errors() {
+    use std::io::{self, ErrorKind};
+
+    let kind = |e: Error| io::Error::from(e).kind();
+
+    let decode = serde_json::from_str::<User>("{").unwrap_err();
+    assert_eq!(kind(decode.into()), ErrorKind::InvalidData);
+    assert_eq!(
+        kind(Error::Codeforces("Call limit exceeded".into())),
+        ErrorKind::Other
+    );
+    assert_eq!(kind(Error::Shutdown), ErrorKind::NotConnected);
+
+    let refused = reqwest::get("http://127.0.0.1:1/").await.unwrap_err();
+    assert_eq!(kind(refused.into()), ErrorKind::ConnectionRefused);
+
+    // A server that never answers.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    let timed_out = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_millis(50))
+        .build()
+        .unwrap()
+        .get(url)
+        .send()
+        .await
+        .unwrap_err();
+    assert_eq!(kind(timed_out.into()), ErrorKind::TimedOut);
+
+    // The original error is kept.
+    let e = io::Error::from(Error::Shutdown);
+    assert!(matches!(
+        e.into_inner().unwrap().downcast_ref::<Error>(),
+        Some(Error::Shutdown)
+    ));
+}
+
+#[test]
+pub fn submissions_dedup_by_id() {
+    let sub = |id| Submission {
+        id,
+        ..submission_fixture()
+    };
+    // Pages of 3, where a new submission shifted the second page by one.
+    let mut subs: Vec<_> = (3..6).rev().map(sub).collect();
+    subs.extend((1..4).rev().map(sub));
+
+    let mut changed = sub(3);
+    changed.verdict = Some(Verdict::Ok);
+    assert_eq!(changed, subs[2]);
+
+    let ids: Vec<_> = dedup_submissions(subs).iter().map(|s| s.id).collect();
+    assert_eq!(ids, [5, 4, 3, 2, 1]);
+}
+
+#[tokio::test]
+pub async fn paginate_fetches_pages_until_a_short_one() {
+    use futures_util::StreamExt;
+    use std::cell::RefCell;
+
+    let calls = RefCell::new(Vec::new());
+    let page_fn = |fail_at: Option<u64>| {
+        let calls = &calls;
+        move |from: u64, count: u64| {
+            calls.borrow_mut().push((from, count));
+            async move {
+                if Some(from) == fail_at {
+                    return Err(Error::Codeforces("Call limit exceeded".into()));
+                }
+                Ok((from..(from + count).min(11)).collect::<Vec<_>>())
+            }
+        }
+    };
+
+    let items: Vec<_> = paginate(page_fn(None), 4)
+        .map(|v| v.unwrap())
+        .collect()
+        .await;
+    assert_eq!(items, (1..11).collect::<Vec<_>>());
+    assert_eq!(*calls.borrow(), [(1, 4), (5, 4), (9, 4)]);
+
+    // A full last page needs an empty page to end.
+    calls.borrow_mut().clear();
+    let items: Vec<_> = paginate(page_fn(None), 5).collect().await;
+    assert_eq!(items.len(), 10);
+    assert_eq!(*calls.borrow(), [(1, 5), (6, 5), (11, 5)]);
+
+    // Errors end the stream.
+    calls.borrow_mut().clear();
+    let items: Vec<_> = paginate(page_fn(Some(5)), 4).collect().await;
+    assert_eq!(items.len(), 5);
+    assert!(items[..4].iter().all(|v| v.is_ok()));
+    assert!(items[4].is_err());
+    assert_eq!(calls.borrow().len(), 2);
+}
+
+#[test]
+pub fn problem_indices_natural_order() {
+    let mut indices = vec!["B", "A10", "C", "A2", "A", "B1", "A1", "AA"];
+    indices.sort_by(|a, b| problem_index_cmp(a, b));
+    assert_eq!(indices, ["A", "A1", "A2", "A10", "AA", "B", "B1", "C"]);
+
+    // ProblemIndex sorts the same way.
+    let mut typed: Vec<ProblemIndex> = ["B", "A10", "C", "A2", "A"]
+        .iter()
+        .map(|&i| i.into())
+        .collect();
+    typed.sort();
+    let typed: Vec<_> = typed.iter().map(|i| i.0.as_str()).collect();
+    assert_eq!(typed, ["A", "A2", "A10", "B", "C"]);
+    assert!(ProblemIndex::from("A2") < ProblemIndex::from("A10"));
+
+    // Standings keep the problems in display order.
+    let standings = standings_fixture();
+    assert!(standings
+        .problems
+        .windows(2)
+        .all(|w| problem_index_cmp(&w[0].index, &w[1].index).is_lt()));
+}
+
+#[tokio::test]
+pub async fn clones_share_the_rate_limit() {
+    use std::time::{Duration, Instant};
+
+    let ok = r#"{"status": "OK", "result": []}"#;
+    let (url, requests) = serve(vec![(200, ok), (200, ok), (200, ok)]).await;
+    let http = Client::builder()
+        .base_url(url)
+        .rate_limit(1, Duration::from_millis(200))
+        .build()
+        .unwrap();
+    let other = http.clone();
+
+    let start = Instant::now();
+    let (a, b) = futures_util::join!(
+        User::rating(&http, "natsukagami"),
+        User::rating(&other, "natsukagami")
+    );
+    a.unwrap();
+    b.unwrap();
+    // The second request waited for the first one's slot.
+    assert!(start.elapsed() >= Duration::from_millis(200));
+    assert_eq!(requests.lock().unwrap().len(), 2);
+    assert_eq!(http.total_wait(), other.total_wait());
+    assert!(http.total_wait() >= Duration::from_millis(150));
+
+    other.shutdown().await;
+    assert!(matches!(
+        User::rating(&http, "natsukagami").await,
+        Err(Error::Shutdown)
+    ));
+}
+
+#[test]
+pub fn rating_change_deltas_and_streaks() {
+    let mut history = rating_history_fixture();
+    let deltas: Vec<_> = history.iter().map(|c| c.delta()).collect();
+    assert_eq!(deltas, [1500, 120, -40, 120]);
+    assert!(history[1].is_positive());
+    assert!(!history[2].is_positive());
+
+    assert_eq!(rating_streak(&history), 1);
+    assert_eq!(rating_streak(&history[..3]), -1);
+    assert_eq!(rating_streak(&history[..2]), 2);
+    assert_eq!(rating_streak(&[]), 0);
+
+    history[3].new_rating = history[3].old_rating;
+    assert_eq!(rating_streak(&history), 0);
+    history[3].new_rating = 1500;
+    history[2].new_rating = 1580;
+    assert_eq!(rating_streak(&history), -2);
+}
+
+#[test]
+pub fn recommend_problems_near_target() {
+    let pool: Vec<Problem> = [
+        (1, "A", 1600),
+        (1, "B", 1500),
+        (2, "A", 1400),
+        (2, "B", 1700),
+        (3, "A", 1450),
+        (3, "B", 2000),
+        (4, "A", 1550),
+    ]
+    .iter()
+    .map(|&(contest, index, rating)| {
+        serde_json::from_value(problem_json(contest, index, rating, &[])).unwrap()
+    })
+    .collect();
+
+    let solve = |contest, index: &str, verdict| {
+        let mut s = submission_fixture();
+        s.problem = pool
+            .iter()
+            .find(|p| p.identity() == (Some(contest), index))
+            .unwrap()
+            .clone();
+        s.verdict = Some(verdict);
+        s
+    };
+    let solved = [
+        solve(1, "B", Verdict::Ok),
+        // Not accepted, so still recommended.
+        solve(3, "A", Verdict::WrongAnswer),
+    ];
+
+    let ids = |ps: Vec<&Problem>| -> Vec<_> {
+        ps.iter()
+            .map(|p| (p.contest_id.unwrap(), p.index.clone()))
+            .collect()
+    };
+    assert_eq!(
+        ids(recommend_problems(&solved, &pool, 1500, 3)),
+        [
+            (3, "A".to_owned()),
+            (4, "A".to_owned()),
+            (1, "A".to_owned())
+        ]
+    );
+    // 2000 is too far from the target.
+    assert_eq!(recommend_problems(&solved, &pool, 1500, 10).len(), 5);
+    assert!(recommend_problems(&solved, &pool, 3000, 10).is_empty());
+}
+
+#[test]
+pub fn attempts_until_accepted() {
+    let sub = |id, time, verdict| Submission {
+        id,
+        creation_time_seconds: time,
+        verdict: Some(verdict),
+        ..submission_fixture()
+    };
+
+    assert_eq!(attempts_until_ac(&[]), None);
+    assert_eq!(attempts_until_ac(&[sub(1, 100, Verdict::Ok)]), Some(1));
+    assert_eq!(
+        attempts_until_ac(&[
+            sub(1, 100, Verdict::WrongAnswer),
+            sub(2, 200, Verdict::TimeLimitExceeded)
+        ]),
+        None
+    );
+    // Newest first, as returned by `user.status`.
+    assert_eq!(
+        attempts_until_ac(&[
+            sub(4, 400, Verdict::WrongAnswer),
+            sub(3, 300, Verdict::Ok),
+            sub(2, 200, Verdict::Ok),
+            sub(1, 100, Verdict::WrongAnswer),
+        ]),
+        Some(2)
+    );
+}
+
+#[tokio::test]
+pub async fn participant_count_counts_rows() {
+    let mut responses = std::collections::HashMap::new();
+    responses.insert(
+        "https://codeforces.com/api/contest.standings?contestId=1&allowOfficial=false".to_owned(),
+        format!(r#"{{"status": "OK", "result": {}}}"#, STANDINGS_JSON),
+    );
+    let http = Client::mock(responses);
+    assert_eq!(Contest::participant_count(&http, 1).await.unwrap(), 3);
+}
+
+#[test]
+pub fn submission_passed_fraction() {
+    let mut sub = submission_fixture();
+    assert_eq!(sub.passed_fraction(), None);
+
+    sub.verdict = Some(Verdict::Testing);
+    assert_eq!(sub.passed_fraction(), None);
+    sub.verdict = None;
+    assert_eq!(sub.passed_fraction(), None);
+
+    sub.verdict = Some(Verdict::Ok);
+    assert_eq!(sub.passed_fraction(), Some(1.0));
+}
+
+#[tokio::test]
+pub async fn forbidden_errors_are_classified() {
+    let mut responses = std::collections::HashMap::new();
+    responses.insert(
+        "contest.standings".to_owned(),
+        r#"{"status":"FAILED","comment":"contestId: You are not allowed to view the contest"}"#
+            .to_owned(),
+    );
+    responses.insert(
+        "contest.hacks".to_owned(),
+        r#"{"status":"FAILED","comment":"contestId: Contest with id 100000 not found"}"#.to_owned(),
+    );
+    let http = Client::mock(responses);
+
+    match Contest::standings(&http, 1, |b| b).await {
+        Err(Error::Forbidden(e)) => {
+            assert_eq!(
+                e.comment,
+                "contestId: You are not allowed to view the contest"
+            );
+            assert_eq!(e.method.as_deref(), Some("contest.standings"));
+        }
+        v => panic!("expected a forbidden error, got {:?}", v),
+    }
+    assert_eq!(
+        std::io::Error::from(Error::Forbidden("You have no access".into())).kind(),
+        std::io::ErrorKind::PermissionDenied
+    );
+
+    let other: Result<Vec<User>> = http.get("contest.hacks", &[]).await;
+    assert!(matches!(other, Err(Error::Codeforces(_))));
+}
+
+#[tokio::test]
+pub async fn client_builder_applies_every_option() {
+    use std::time::{Duration, Instant};
+
+    let limited = r#"{"status": "FAILED", "comment": "Call limit exceeded"}"#;
+    let (url, requests) = serve(vec![
+        (200, limited),
+        (200, limited),
+        (200, r#"{"status": "OK", "result": []}"#),
+    ])
+    .await;
+    let http = Client::builder()
+        .base_url(url.clone() + "/")
+        .lang("ru")
+        .timeout(Duration::from_secs(5))
+        .call_limit_retries(1)
+        .rate_limit(2, Duration::from_millis(100))
+        .rate_limit_mode(RateLimitMode::Burst)
+        .concurrency_limit(1)
+        .cache_finished_standings(true)
+        .http2_prior_knowledge(false)
+        .pool_max_idle_per_host(1)
+        .tcp_keepalive(Some(Duration::from_secs(60)))
+        .build()
+        .unwrap();
+    assert_eq!(http.base_url(), url);
+
+    // Retried once, then the error is returned.
+    let start = Instant::now();
+    let e = User::rating(&http, "natsukagami").await.unwrap_err();
+    assert!(e.is_call_limit_exceeded(), "{:?}", e);
+
+    assert!(User::rating(&http, "natsukagami").await.unwrap().is_empty());
+    // The third request waited for the rate limit.
+    assert!(start.elapsed() >= Duration::from_millis(100));
+
+    let requests = requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 3);
+    assert!(requests
+        .iter()
+        .all(|r| r.starts_with("GET /api/user.rating?handle=natsukagami&lang=ru ")));
+
+    // A server that never answers.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let http = Client::builder()
+        .base_url(format!("http://{}/api", listener.local_addr().unwrap()))
+        .timeout(Duration::from_millis(50))
+        .build()
+        .unwrap();
+    match User::rating(&http, "natsukagami").await {
+        Err(Error::Http(e)) => assert!(e.is_timeout()),
+        v => panic!("expected a timeout, got {:?}", v),
+    }
+}
+
+#[test]
+pub fn problem_rating_colors() {
+    let colors: Vec<_> = [
+        800, 1199, 1200, 1400, 1600, 1899, 1900, 2100, 2300, 2399, 2400, 3500,
+    ]
+    .iter()
+    .map(|&r| problem_rating_color(r))
+    .collect();
+    assert_eq!(
+        colors,
+        [
+            0x808080, 0x808080, 0x008000, 0x03a89e, 0x0000ff, 0x0000ff, 0xaa00aa, 0xff8c00,
+            0xff8c00, 0xff8c00, 0xff0000, 0xff0000
+        ]
+    );
+
+    // Users get a different color at 2100.
+    let mut user = user_fixture("alice");
+    user.rating = Some(2100);
+    assert_ne!(user.color(), problem_rating_color(2100));
+
+    let mut problem: Problem = serde_json::from_value(problem_json(1, "A", 1500, &[])).unwrap();
+    assert_eq!(problem.color(), Some(0x03a89e));
+    problem.rating = None;
+    assert_eq!(problem.color(), None);
+}
+
+#[tokio::test]
+pub async fn cancelling_stops_streams_and_releases_permits() {
+    use futures_util::StreamExt;
+    use std::time::{Duration, Instant};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Never answers the first request, then answers with the standings.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/api", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (hanging, _) = listener.accept().await.unwrap();
+        let (mut conn, _) = listener.accept().await.unwrap();
+        let mut buf = [0u8; 1024];
+        let _ = conn.read(&mut buf).await.unwrap();
+        let body = format!(r#"{{"status": "OK", "result": {}}}"#, STANDINGS_JSON);
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        conn.write_all(response.as_bytes()).await.unwrap();
+        drop(hanging);
+    });
+
+    let http = Client::builder()
+        .base_url(url)
+        .rate_limit(1, Duration::from_millis(50))
+        .build()
+        .unwrap();
+    let token = CancellationToken::new();
+    let canceller = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        canceller.cancel();
+    });
+
+    let start = Instant::now();
+    let watched: Vec<_> = cancellable(
+        Contest::watch_standings(&http, 1, Duration::from_secs(60)),
+        token,
+    )
+    .collect()
+    .await;
+    assert!(watched.is_empty());
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    // The permit held by the cancelled request is given back.
+    let (contest, _, _) =
+        tokio::time::timeout(Duration::from_secs(1), Contest::standings(&http, 1, |b| b))
+            .await
+            .unwrap()
+            .unwrap();
+    assert_eq!(contest.id, 1);
+
+    // Streams end right away once cancelled.
+    let token = CancellationToken::new();
+    token.cancel();
+    let items: Vec<u64> = cancellable(stream::iter(vec![1, 2, 3]), token)
+        .collect()
+        .await;
+    assert!(items.is_empty());
+}
+
+#[tokio::test]
+pub async fn submissions_of_one_contest() {
+    let sub = |id, contest_id| Submission {
+        id,
+        contest_id,
+        ..submission_fixture()
+    };
+    let subs = [
+        sub(1, Some(1)),
+        sub(2, Some(2)),
+        sub(3, None),
+        sub(4, Some(1)),
+    ];
+    let ids: Vec<_> = filter_by_contest(&subs, 1).iter().map(|s| s.id).collect();
+    assert_eq!(ids, [1, 4]);
+    assert!(filter_by_contest(&subs, ContestId(3)).is_empty());
+
+    let mut responses = std::collections::HashMap::new();
+    responses.insert(
+        "https://codeforces.com/api/contest.status?contestId=1&handle=alice".to_owned(),
+        r#"{"status": "OK", "result": []}"#.to_owned(),
+    );
+    let http = Client::mock(responses);
+    assert!(Contest::user_status(&http, 1, "alice")
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(Contest::user_status(&http, 1, "no").await.is_err());
+}
+
+#[tokio::test]
+pub async fn metrics_count_calls() {
+    let responses = [
+        ("user.info", r#"{"status": "OK", "result": []}"#),
+        (
+            "user.rating",
+            r#"{"status": "FAILED", "comment": "Call limit exceeded"}"#,
+        ),
+        ("contest.list", r#"{"status": "OK", "result": "#),
+        (
+            "contest.standings",
+            r#"{"status": "FAILED", "comment": "contestId: You are not allowed to view the contest"}"#,
+        ),
+    ];
+    let http = Client::mock(
+        responses
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+    );
+    assert_eq!(http.metrics(), Metrics::default());
+
+    User::info(&http, &["alice"]).await.unwrap();
+    User::rating(&http, "alice").await.unwrap_err();
+    Contest::list(&http, false).await.unwrap_err();
+    Contest::standings(&http, 1, |b| b).await.unwrap_err();
+    // Rejected before sending anything.
+    User::rating(&http, "no").await.unwrap_err();
+
+    assert_eq!(
+        http.clone().metrics(),
+        Metrics {
+            requests: 7,
+            retries: 3,
+            succeeded: 1,
+            http_errors: 0,
+            decode_errors: 1,
+            codeforces_errors: 1,
+            forbidden_errors: 1,
+            shutdown_errors: 0,
+            middleware_errors: 0,
+            local_errors: 0,
+        }
+    );
+}
+
+#[test]
+pub fn submissions_in_time_window() {
+    let subs: Vec<_> = [100, 200, 300, 400]
+        .iter()
+        .map(|&time| Submission {
+            id: time,
+            creation_time_seconds: time,
+            ..submission_fixture()
+        })
+        .collect();
+    let ids = |subs: Vec<&Submission>| -> Vec<u64> { subs.iter().map(|s| s.id).collect() };
+
+    assert_eq!(ids(submissions_between(&subs, 200, 300)), [200, 300]);
+    assert_eq!(ids(submissions_between(&subs, 201, 299)), Vec::<u64>::new());
+    assert_eq!(ids(submissions_between(&subs, 300, 200)), Vec::<u64>::new());
+    assert_eq!(ids(submissions_between(&subs, 0, 100)), [100]);
+
+    assert!(subs[1].submitted_within(300, 100));
+    assert!(subs[1].submitted_within(200, 0));
+    assert!(!subs[1].submitted_within(301, 100));
+    assert!(!subs[1].submitted_within(199, 100));
+    assert!(subs[0].submitted_within(150, 1000));
+}
+
+#[tokio::test]
+pub async fn error_status_with_json_body() {
+    let (url, _) = serve(vec![
+        (
+            400,
+            r#"{"status": "FAILED", "comment": "handle: User with handle nobody_here not found"}"#,
+        ),
+        (502, "<html>Bad Gateway</html>"),
+    ])
+    .await;
+    let http = Client::with_base_url(url);
+
+    match User::rating(&http, "nobody_here").await {
+        Err(Error::Codeforces(e)) => {
+            assert_eq!(e.comment, "handle: User with handle nobody_here not found");
+            assert_eq!(e.method.as_deref(), Some("user.rating"));
+        }
+        r => panic!("unexpected {:?}", r),
+    }
+    match User::rating(&http, "nobody_here").await {
+        Err(Error::Http(e)) => assert_eq!(e.status(), Some(reqwest::StatusCode::BAD_GATEWAY)),
+        r => panic!("unexpected {:?}", r),
+    }
+}
+
+#[tokio::test]
+pub async fn user_info_keeps_input_order() {
+    let (url, _) = serve(vec![(
+        200,
+        r#"{"status": "OK", "result": [
+            {"handle": "carol", "contribution": 0, "lastOnlineTimeSeconds": 1, "registrationTimeSeconds": 1, "friendOfCount": 0, "avatar": "", "titlePhoto": ""},
+            {"handle": "Alice", "contribution": 0, "lastOnlineTimeSeconds": 1, "registrationTimeSeconds": 1, "friendOfCount": 0, "avatar": "", "titlePhoto": ""},
+            {"handle": "bob", "contribution": 0, "lastOnlineTimeSeconds": 1, "registrationTimeSeconds": 1, "friendOfCount": 0, "avatar": "", "titlePhoto": ""}
+        ]}"#,
+    )])
+    .await;
+    let http = Client::with_base_url(url);
+
+    let handles = ["alice", "BOB", "carol"];
+    let users = User::info(&http, &handles).await.unwrap();
+    let got: Vec<_> = users.iter().map(|u| u.handle.as_str()).collect();
+    assert_eq!(got, ["Alice", "bob", "carol"]);
+    for (handle, user) in handles.iter().zip(&users) {
+        assert!(handle.eq_ignore_ascii_case(&user.handle));
+    }
+}
+
+#[tokio::test]
+pub async fn top_standings_returns_at_most_n_rows() {
+    let mut responses = std::collections::HashMap::new();
+    let body = format!(r#"{{"status": "OK", "result": {}}}"#, STANDINGS_JSON);
+    for count in &[1, 2, 10] {
+        responses.insert(
+            format!(
+                "https://codeforces.com/api/contest.standings?contestId=1&allowOfficial=false&from=1&count={}",
+                count
+            ),
+            body.clone(),
+        );
+    }
+    let http = Client::mock(responses);
+
+    for &(n, expected) in &[(0, 0), (2, 2), (10, 3)] {
+        let (_, _, rows) = Contest::top_standings(&http, 1, n).await.unwrap();
+        assert_eq!(rows.len(), expected);
+    }
+}
+
+#[test]
+pub fn contests_are_equal_by_id() {
+    let finished = standings_fixture().contest;
+    let mut coding = finished.clone();
+    coding.phase = ContestPhase::Coding;
+    coding.frozen = true;
+    assert_eq!(finished, coding);
+
+    let mut other = finished.clone();
+    other.id = 2;
+    assert_ne!(finished, other);
+
+    let set: std::collections::HashSet<_> = vec![finished, coding, other].into_iter().collect();
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+pub fn submission_official_filter() {
+    use ParticipantType::*;
+    let sub = |participant_type, test_set| {
+        let mut sub = Submission {
+            test_set,
+            ..submission_fixture()
+        };
+        sub.author.participant_type = participant_type;
+        sub
+    };
+
+    let official: Vec<_> = [Contestant, Practice, Virtual, Manager, OutOfCompetition]
+        .iter()
+        .map(|&t| sub(t, SubmissionTestSet::Tests).is_official())
+        .collect();
+    assert_eq!(official, [true, false, false, false, false]);
+    assert!(!sub(Contestant, SubmissionTestSet::Pretests).is_official());
+}
+
+#[test]
+pub fn borrowed_users_convert_to_owned() {
+    use borrowed::*;
+    use std::borrow::Cow;
+
+    let body = r#"{"status": "OK", "result": [
+        {"handle": "tourist", "firstName": "Gennady", "country": "Belarus", "contribution": 100,
+         "rank": "legendary grandmaster", "rating": 3800, "lastOnlineTimeSeconds": 1,
+         "registrationTimeSeconds": 2, "friendOfCount": 3, "avatar": "//a.jpg",
+         "titlePhoto": "\/\/t.jpg"}
+    ]}"#;
+    let users: Vec<UserRef> = decode(body).unwrap();
+    assert!(matches!(users[0].handle, Cow::Borrowed("tourist")));
+    // Escaped strings are unescaped into an owned copy.
+    assert!(matches!(users[0].title_photo, Cow::Owned(ref s) if s == "//t.jpg"));
+
+    let user = users[0].clone().into_owned();
+    assert_eq!(user.handle, "tourist");
+    assert_eq!(user.first_name.as_deref(), Some("Gennady"));
+    assert_eq!(user.last_name, None);
+    assert_eq!(user.rating, Some(3800));
+    assert_eq!(user.title_photo_url(), "https://t.jpg");
+
+    let changes: Vec<RatingChangeRef> = decode(
+        r#"{"status": "OK", "result": [{"contestId": 1, "contestName": "Round 1", "handle": "tourist",
+            "rank": 1, "ratingUpdateTimeSeconds": 5, "oldRating": 1500, "newRating": 1700}]}"#,
+    )
+    .unwrap();
+    let change: RatingChange = changes[0].clone().into();
+    assert_eq!(change.contest_name, "Round 1");
+    assert_eq!(change.delta(), 200);
+
+    match decode::<Vec<UserRef>>(
+        r#"{"status": "FAILED", "comment": "handles: User with handle x not found"}"#,
+    ) {
+        Err(Error::Codeforces(e)) => assert_eq!(e.comment, "handles: User with handle x not found"),
+        r => panic!("unexpected {:?}", r),
+    }
+}
+
+#[tokio::test]
+pub async fn request_hook_fires_once_per_call() {
+    let ok = r#"{"status": "OK", "result": []}"#;
+    let (url, _) = serve(vec![(200, ok), (200, ok)]).await;
+    let calls = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let seen = calls.clone();
+    let http = Client::builder()
+        .base_url(url)
+        .rate_limit(10, std::time::Duration::from_millis(1))
+        .on_request(move |method, params| {
+            seen.lock()
+                .unwrap()
+                .push((method.to_owned(), params.to_vec()))
+        })
+        .build()
+        .unwrap();
+
+    User::rating(&http, "tourist").await.unwrap();
+    Contest::list(&http, false).await.unwrap();
+
+    let calls = calls.lock().unwrap();
+    assert_eq!(
+        *calls,
+        [
+            (
+                "user.rating".to_owned(),
+                vec![("handle".to_owned(), "tourist".to_owned())]
+            ),
+            (
+                "contest.list".to_owned(),
+                vec![("gym".to_owned(), "false".to_owned())]
+            ),
+        ]
+    );
+}
+
+#[test]
+pub fn ranklist_penalty_display() {
+    let mut row = standings_fixture().rows[0].clone();
+    row.points = 2300.0;
+    row.penalty = 125;
+    assert_eq!(row.penalty_display(ContestType::ICPC), "2:05");
+    assert_eq!(row.penalty_display(ContestType::CF), "2300");
+    row.points = 12.5;
+    assert_eq!(row.penalty_display(ContestType::IOI), "12.5");
+    row.penalty = 0;
+    assert_eq!(row.penalty_display(ContestType::ICPC), "0:00");
+    row.penalty = 601;
+    assert_eq!(row.penalty_display(ContestType::ICPC), "10:01");
+}
+
+#[tokio::test]
+pub async fn contest_get_many_keeps_order() {
+    let contest = |id| {
+        format!(
+            r#"{{"id": {}, "name": "Contest {}", "type": "CF", "phase": "FINISHED", "frozen": false, "durationSeconds": 7200}}"#,
+            id, id
+        )
+    };
+    let list = |ids: &[u64]| {
+        let contests: Vec<_> = ids.iter().map(|&id| contest(id)).collect();
+        format!(r#"{{"status": "OK", "result": [{}]}}"#, contests.join(","))
+    };
+    let mut responses = std::collections::HashMap::new();
+    responses.insert(
+        "https://codeforces.com/api/contest.list?gym=false".to_owned(),
+        list(&[3, 2, 1]),
+    );
+    responses.insert(
+        "https://codeforces.com/api/contest.list?gym=true".to_owned(),
+        list(&[100001]),
+    );
+    let http = Client::mock(responses);
+
+    let ids = |contests: Vec<Contest>| -> Vec<u64> { contests.iter().map(|c| c.id).collect() };
+    let (found, missing) = Contest::get_many(&http, &[1, 3]).await.unwrap();
+    assert_eq!(ids(found), [1, 3]);
+    assert!(missing.is_empty());
+
+    let (found, missing) = Contest::get_many(&http, &[100001, 4, 2]).await.unwrap();
+    assert_eq!(ids(found), [100001, 2]);
+    assert_eq!(missing, [ContestId(4)]);
+}
+
+#[tokio::test]
+pub async fn error_http_accessors() {
+    let http = Client::with_base_url("http://127.0.0.1:1/api".to_owned());
+    let err = User::rating(&http, "tourist").await.unwrap_err();
+    let url = err.as_http().and_then(|e| e.url()).unwrap();
+    assert_eq!(url.path(), "/api/user.rating");
+    assert!(err.into_http().unwrap().is_connect());
+
+    let err = Error::Codeforces("handle: not found".into());
+    assert!(err.as_http().is_none());
+    assert!(err.into_http().is_none());
+    assert!(Error::Shutdown.into_http().is_none());
+}
+
+#[tokio::test]
+pub async fn rated_list_stream_yields_all_users() {
+    use futures_util::StreamExt;
+
+    let handles = ["tourist", "Benq", "jiangly"];
+    let users: Vec<_> = handles
+        .iter()
+        .map(|h| {
+            serde_json::json!({
+                "handle": h, "contribution": 0, "lastOnlineTimeSeconds": 1,
+                "registrationTimeSeconds": 1, "friendOfCount": 0, "avatar": "", "titlePhoto": "",
+            })
+            .to_string()
+        })
+        .collect();
+    let mut responses = std::collections::HashMap::new();
+    responses.insert(
+        "https://codeforces.com/api/user.ratedList?activeOnly=true".to_owned(),
+        format!(
+            r#"{{"status": "OK", "result": [ {} ] }}"#,
+            users.join(" ,\n ")
+        ),
+    );
+    responses.insert(
+        "https://codeforces.com/api/user.ratedList?activeOnly=false".to_owned(),
+        r#"{"result": [], "status": "OK"}"#.to_owned(),
+    );
+    let http = Client::mock(responses);
+
+    let streamed: Vec<_> = User::rated_list_stream(&http, true)
+        .map(|u| u.unwrap().handle)
+        .collect()
+        .await;
+    assert_eq!(streamed, handles);
+    assert_eq!(User::rated_list_stream(&http, false).count().await, 0);
+
+    let http = Client::mock(std::collections::HashMap::new());
+    let results: Vec<_> = User::rated_list_stream(&http, true).collect().await;
+    assert!(matches!(results[..], [Err(Error::Local(_))]));
+
+    let mut responses = std::collections::HashMap::new();
+    responses.insert(
+        "user.ratedList".to_owned(),
+        r#"{"status": "FAILED", "comment": "Internal error"}"#.to_owned(),
+    );
+    let http = Client::mock(responses);
+    let results: Vec<_> = User::rated_list_stream(&http, true).collect().await;
+    match &results[..] {
+        [Err(Error::Codeforces(e))] => {
+            assert_eq!(e.comment, "Internal error");
+            assert_eq!(e.method.as_deref(), Some("user.ratedList"));
+        }
+        _ => panic!("expected the comment"),
+    }
+}
+
+#[test]
+pub fn bulk_decoding_matches_serde_json() {
+    let raw = format!(r#"{{"status": "OK", "result": {}}}"#, STANDINGS_JSON);
+    let bulk: Standings = decode_bulk_response(raw.as_bytes()).unwrap();
+    let plain: Standings = decode_response(raw.as_bytes()).unwrap();
+    let names = |s: &Standings| -> Vec<String> {
+        s.rows.iter().map(|r| r.party.name().to_owned()).collect()
+    };
+    assert_eq!(names(&bulk), names(&plain));
+    assert_eq!(bulk.contest.name, plain.contest.name);
+
+    let raw = br#"{"status": "FAILED", "comment": "activeOnly: invalid"}"#;
+    assert!(matches!(
+        decode_bulk_response::<Vec<User>>(raw),
+        Err(Error::Codeforces(_))
+    ));
+    assert!(matches!(
+        decode_bulk_response::<Vec<User>>(b"{\"result\": [1]}"),
+        Err(Error::Decode(_))
+    ));
+}
+
+#[test]
+pub fn result_items_stop_at_the_first_error() {
+    let raw = br#"{"status": "OK", "result": [1, 2, "three", 4]}"#.to_vec();
+    let items: Vec<_> = ResultItems::<u64>::new(raw).unwrap().collect();
+    assert!(matches!(items[..], [Ok(1), Ok(2), Err(Error::Decode(_))]));
+
+    let raw = br#"{"status": "OK", "result": {"id": 1}}"#.to_vec();
+    assert!(matches!(
+        ResultItems::<u64>::new(raw),
+        Err(Error::Decode(_))
+    ));
+
+    let raw = br#"{"status": "FAILED", "comment": "activeOnly: invalid"}"#.to_vec();
+    match ResultItems::<u64>::new(raw) {
+        Err(Error::Codeforces(e)) => assert_eq!(e.comment, "activeOnly: invalid"),
+        _ => panic!("expected the comment"),
+    }
+}
+
+#[tokio::test]
+pub async fn current_rating_of_rated_and_unrated() {
+    let user = |handle: &str, rating: Option<i64>| {
+        let mut user = serde_json::json!({
+            "handle": handle, "contribution": 0, "lastOnlineTimeSeconds": 1,
+            "registrationTimeSeconds": 1, "friendOfCount": 0, "avatar": "", "titlePhoto": "",
+        });
+        if let Some(rating) = rating {
+            user["rating"] = rating.into();
+        }
+        format!(r#"{{"status": "OK", "result": [{}]}}"#, user)
+    };
+    let mut responses = std::collections::HashMap::new();
+    responses.insert(
+        "https://codeforces.com/api/user.info?handles=tourist".to_owned(),
+        user("tourist", Some(3800)),
+    );
+    responses.insert(
+        "https://codeforces.com/api/user.info?handles=newbie".to_owned(),
+        user("newbie", None),
+    );
+    let http = Client::mock(responses);
+
+    assert_eq!(
+        User::current_rating(&http, "tourist").await.unwrap(),
+        Some(3800)
+    );
+    assert_eq!(User::current_rating(&http, "newbie").await.unwrap(), None);
+}
+
+#[test]
+pub fn standings_ghosts_and_live_participants() {
+    let mut standings = standings_fixture();
+    // Ghosts have no members on Codeforces, only the original team name.
+    let ghost = &mut standings.rows[1].party;
+    ghost.ghost = true;
+    ghost.members.clear();
+
+    let ranks = |rows: Vec<&RanklistRow>| -> Vec<u64> { rows.iter().map(|r| r.rank).collect() };
+    assert_eq!(ranks(standings.ghosts()), [2]);
+    assert_eq!(ranks(standings.live_participants()), [1, 3]);
+    assert_eq!(
+        standings.ghosts()[0].party.name(),
+        "Bob, Carol and \"friends\""
+    );
+}
+
+#[tokio::test]
+pub async fn problems_with_stats_joins_by_problem() {
+    let problems = [
+        problem_json(2, "A", 800, &["dp"]),
+        problem_json(1, "A", 1200, &["dp"]),
+        problem_json(1, "B", 1600, &["dp"]),
+    ];
+    let stats = [
+        ((1, "B"), 30),
+        ((2, "A"), 10),
+        ((1, "A"), 20),
+        ((3, "A"), 99),
+    ];
+    let stats: Vec<_> = stats
+        .iter()
+        .map(|&((c, i), n)| serde_json::json!({"contestId": c, "index": i, "solvedCount": n}))
+        .collect();
+    // The last problem has no statistics.
+    let problems: Vec<_> = problems
+        .iter()
+        .cloned()
+        .chain(Some(problem_json(4, "C", 2000, &["dp"])))
+        .collect();
+    let body = serde_json::json!({"status": "OK", "result": {"problems": problems, "problemStatistics": stats}});
+    let mut responses = std::collections::HashMap::new();
+    responses.insert(
+        "https://codeforces.com/api/problemset.problems?tags=dp".to_owned(),
+        body.to_string(),
+    );
+    let http = Client::mock(responses);
+
+    let joined = Problemset::problems_with_stats(&http, &["dp"])
+        .await
+        .unwrap();
+    let got: Vec<_> = joined
+        .iter()
+        .map(|p| {
+            (
+                p.problem.contest_id.unwrap(),
+                p.problem.index.as_str(),
+                p.solved_count,
+            )
+        })
+        .collect();
+    assert_eq!(got, [(2, "A", 10), (1, "A", 20), (1, "B", 30), (4, "C", 0)]);
+}
+
+#[tokio::test]
+pub async fn import_pages_reports_progress() {
+    use std::cell::RefCell;
+
+    // Newest first: 7 submissions, with a new one arriving after the first page.
+    let history = RefCell::new((1..=7).rev().collect::<Vec<u64>>());
+    let fetch = |fail_at: Option<u64>| {
+        let history = &history;
+        move |from: u64, count: u64| {
+            let page: Vec<_> = history
+                .borrow()
+                .iter()
+                .skip(from as usize - 1)
+                .take(count as usize)
+                .map(|&id| Submission {
+                    id,
+                    ..submission_fixture()
+                })
+                .collect();
+            history.borrow_mut().insert(0, 100 + from);
+            async move {
+                match fail_at {
+                    Some(f) if f == from => Err(Error::Codeforces("Call limit exceeded".into())),
+                    _ => Ok(page),
+                }
+            }
+        }
+    };
+
+    let mut counts = Vec::new();
+    let (subs, err) = import_pages(fetch(None), 3, |n| counts.push(n)).await;
+    assert!(err.is_none());
+    let ids: Vec<_> = subs.iter().map(|s| s.id).collect();
+    // Shifted pages repeat a submission, which is kept once.
+    assert_eq!(ids, [7, 6, 5, 4, 3, 2, 1]);
+    assert_eq!(counts, [3, 5, 7, 7]);
+
+    *history.borrow_mut() = (1..=7).rev().collect();
+    let (subs, err) = import_pages(fetch(Some(4)), 3, |_| ()).await;
+    assert_eq!(subs.len(), 3);
+    assert!(err.unwrap().is_call_limit_exceeded());
+}
+
+#[test]
+pub fn best_submission_breaks_ties() {
+    let sub = |id, verdict, time, memory| Submission {
+        id,
+        creation_time_seconds: id,
+        verdict: Some(verdict),
+        time_consumed_millis: time,
+        memory_consumed_bytes: memory,
+        ..submission_fixture()
+    };
+    let mut other_problem = sub(1, Verdict::Ok, 1, 1);
+    other_problem.problem.index = "B".to_owned();
+    let mut subs = vec![
+        other_problem,
+        sub(2, Verdict::WrongAnswer, 10, 10),
+        sub(3, Verdict::Ok, 50, 2048),
+        sub(4, Verdict::Ok, 30, 4096),
+        sub(5, Verdict::Ok, 30, 1024),
+        sub(6, Verdict::Ok, 30, 1024),
+    ];
+    let best = |subs: &[Submission]| best_submission_for_problem(subs, 1, "A").map(|s| s.id);
+
+    // Same time: least memory, then earliest.
+    assert_eq!(best(&subs), Some(5));
+    subs[4].creation_time_seconds = 7;
+    assert_eq!(best(&subs), Some(6));
+    assert_eq!(best_submission_for_problem(&subs, 1, "B").unwrap().id, 1);
+    assert_eq!(best_submission_for_problem(&subs, 2, "A"), None);
+    let typed = best_submission_for_problem(&subs, ContestId(1), ProblemIndex::from("B"));
+    assert_eq!(typed.unwrap().id, 1);
+    assert_eq!(best(&subs[..3]), Some(3));
+}
+
+#[test]
+pub fn user_and_contest_display() {
+    let mut user = user_fixture("tourist");
+    assert_eq!(user.to_string(), "tourist (unrated)");
+    user.rating = Some(3800);
+    assert_eq!(user.to_string(), "tourist (3800)");
+    user.rank = Some("legendary grandmaster".to_owned());
+    assert_eq!(user.to_string(), "tourist (legendary grandmaster, 3800)");
+
+    let mut contest = standings_fixture().contest;
+    assert_eq!(contest.to_string(), "Test Round (Finished)");
+    contest.phase = ContestPhase::Coding;
+    assert_eq!(
+        contest.to_string(),
+        "Test Round (Contest is currently running)"
+    );
+}
+
+#[tokio::test]
+pub async fn clones_share_the_standings_cache() {
+    let (url, requests) = serve(vec![(200, FINISHED_STANDINGS)]).await;
+    let http = Client::builder()
+        .base_url(url)
+        .cache_finished_standings(true)
+        .build()
+        .unwrap();
+    let (a, b) = (http.clone(), http.clone());
+
+    let (contest, _, _) = Contest::standings(&a, 1, |b| b).await.unwrap();
+    assert_eq!(contest.phase, ContestPhase::Finished);
+    // Answered from the cache populated by the other clone, without waiting
+    // for the rate limit.
+    let (contest, _, _) = tokio::spawn(async move { Contest::standings(&b, 1, |b| b).await })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(contest.id, 1);
+    assert_eq!(requests.lock().unwrap().len(), 1);
+}
+
+#[cfg(feature = "scrape")]
+#[tokio::test]
+pub async fn info_follows_renames() {
+    let response = |status: &str, headers: &str, body: &str| {
+        format!(
+            "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            headers,
+            body.len(),
+            body
+        )
+    };
+    let not_found =
+        r#"{"status": "FAILED", "comment": "handles: User with handle old_name not found"}"#;
+    let user = serde_json::json!({"status": "OK", "result": [{
+        "handle": "new_name", "contribution": 0, "lastOnlineTimeSeconds": 1,
+        "registrationTimeSeconds": 1, "friendOfCount": 0, "avatar": "", "titlePhoto": "",
+    }]});
+    // Recorded from the website: the old profile redirects to the new one,
+    // while unknown profiles redirect to the main page.
+    let (url, requests) = serve_raw(vec![
+        response("400 Bad Request", "", not_found),
+        response(
+            "302 Found",
+            "Location: https://codeforces.com/profile/new_name\r\n",
+            "",
+        ),
+        response("200 OK", "", &user.to_string()),
+        response("400 Bad Request", "", not_found),
+        response("302 Found", "Location: https://codeforces.com/\r\n", ""),
+    ])
+    .await;
+    // The profile request goes through a client with the same settings.
+    let http = Client::builder()
+        .base_url(url)
+        .rate_limit(10, std::time::Duration::from_millis(1))
+        .compression(false)
+        .build()
+        .unwrap();
+
+    let user = User::info_following_renames(&http, "old_name")
+        .await
+        .unwrap();
+    assert_eq!(user.handle, "new_name");
+    match User::info_following_renames(&http, "old_name").await {
+        Err(Error::Codeforces(e)) => assert!(e.comment.contains("not found")),
+        r => panic!("unexpected {:?}", r),
+    }
+
+    let requests = requests.lock().unwrap();
+    assert!(requests[1].starts_with("GET /profile/old_name HTTP/1.1"));
+    assert!(!requests[1].to_ascii_lowercase().contains("accept-encoding"));
+    assert!(requests[2].starts_with("GET /api/user.info?handles=new_name HTTP/1.1"));
+}
+
+#[test]
+pub fn contest_phase_poll_intervals() {
+    use std::time::Duration;
+    use ContestPhase::*;
+    let intervals: Vec<_> = [Before, Coding, PendingSystemTest, SystemTest, Finished]
+        .iter()
+        .map(|p| p.recommended_poll_interval().as_secs())
+        .collect();
+    assert_eq!(intervals, [300, 30, 60, 10, 3600]);
+    assert!(SystemTest.recommended_poll_interval() < Coding.recommended_poll_interval());
+    assert!(Finished.recommended_poll_interval() >= Duration::from_secs(3600));
+}
+
+#[test]
+pub fn submission_verdict_null_or_absent() {
+    let mut json = serde_json::json!({
+        "id": 1, "contestId": 1, "creationTimeSeconds": 1, "relativeTimeSeconds": 1,
+        "problem": {"contestId": 1, "index": "A", "name": "Alpha", "type": "PROGRAMMING", "tags": []},
+        "author": {"contestId": 1, "members": [{"handle": "alice"}], "participantType": "CONTESTANT", "ghost": false},
+        "programmingLanguage": "GNU C++17", "verdict": null, "testset": "TESTS",
+        "passedTestCount": 0, "timeConsumedMillis": 0, "memoryConsumedBytes": 0,
+    });
+    let sub: Submission = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(sub.verdict, None);
+
+    json.as_object_mut().unwrap().remove("verdict");
+    let sub: Submission = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(sub.verdict, None);
+
+    json["verdict"] = "TESTING".into();
+    let sub: Submission = serde_json::from_value(json).unwrap();
+    assert_eq!(sub.verdict, Some(Verdict::Testing));
+}
+
+#[test]
+pub fn rating_series_points() {
+    let mut changes = rating_history_fixture();
+    assert_eq!(changes[1].as_point(), (200, 1620));
+    let expected = [(100, 1500), (200, 1620), (300, 1580), (400, 1700)];
+    assert_eq!(rating_series(&changes), expected);
+    changes.reverse();
+    assert_eq!(rating_series(&changes), expected);
+    assert!(rating_series(&[]).is_empty());
+}
+
+#[tokio::test]
+pub async fn registry_shares_the_rate_limit_between_clients() {
+    use std::time::{Duration, Instant};
+
+    let ok = r#"{"status": "OK", "result": []}"#;
+    let (url, requests) = serve(vec![(200, ok), (200, ok), (200, ok)]).await;
+    let registry = RateLimitRegistry::new();
+    let build = |per| {
+        Client::builder()
+            .base_url(url.clone())
+            .rate_limit(1, Duration::from_millis(per))
+            .rate_limit_registry(&registry)
+            .build()
+            .unwrap()
+    };
+    // The second client's own rate limit is ignored: the host already has one.
+    let (a, b) = (build(200), build(1));
+
+    let start = Instant::now();
+    let (x, y, z) = futures_util::join!(
+        User::rating(&a, "natsukagami"),
+        User::rating(&b, "natsukagami"),
+        User::rating(&b, "natsukagami")
+    );
+    x.unwrap();
+    y.unwrap();
+    z.unwrap();
+    // Three requests at one per 200ms between both clients.
+    assert!(start.elapsed() >= Duration::from_millis(400));
+    assert_eq!(requests.lock().unwrap().len(), 3);
+    assert!(format!("{:?}", registry).contains("127.0.0.1"));
+
+    // Clients of other hosts, or without the registry, have their own.
+    let other = Client::builder()
+        .base_url("http://localhost:1/api")
+        .rate_limit_registry(&registry)
+        .build()
+        .unwrap();
+    a.shutdown().await;
+    assert!(matches!(
+        User::rating(&b, "natsukagami").await,
+        Err(Error::Shutdown)
+    ));
+    assert!(!matches!(
+        User::rating(&other, "natsukagami").await,
+        Err(Error::Shutdown)
+    ));
+
+    // A new client replaces the limit that was shut down.
+    let c = build(1);
+    assert!(!matches!(
+        User::rating(&c, "natsukagami").await,
+        Err(Error::Shutdown)
+    ));
+}
+
+#[test]
+pub fn standings_problem_by_name() {
+    let mut standings = standings_fixture();
+    assert_eq!(standings.problem_by_name("Beta").unwrap().index, "B");
+    assert_eq!(standings.problem_by_name("gAMMA").unwrap().index, "C");
+    assert!(standings.problem_by_name("Delta").is_none());
+
+    standings.problems[2].name = "Alpha".to_owned();
+    assert_eq!(standings.problem_by_name("alpha").unwrap().index, "A");
+}
+
+#[test]
+pub fn user_account_age() {
+    // Registered at 1500000000.
+    let user = user_fixture("alice");
+    assert_eq!(user.account_age_seconds(1500086400), 86400);
+    assert_eq!(user.account_age_seconds(1500000000), 0);
+    assert_eq!(user.account_age_seconds(1400000000), 0);
+
+    assert!(user.registered_before(1500000001));
+    assert!(!user.registered_before(1500000000));
+    assert!(!user.registered_before(1400000000));
+}
+
+#[tokio::test]
+pub async fn try_borrow_timeout_fails_promptly() {
+    use std::time::{Duration, Instant};
+
+    let client = Client::builder()
+        .rate_limit(1, Duration::from_millis(300))
+        .build()
+        .unwrap();
+    let first = client.try_borrow_timeout(Duration::from_secs(1)).await;
+    assert!(first.is_ok());
+
+    // The only slot is taken until the rate limit refills.
+    let start = Instant::now();
+    let err = client
+        .try_borrow_timeout(Duration::from_millis(50))
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::RateLimitTimeout));
+    assert!(start.elapsed() < Duration::from_millis(250));
+    drop(first);
+
+    // The timed out attempt did not use up the refilled slot.
+    assert!(client
+        .try_borrow_timeout(Duration::from_secs(1))
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+pub async fn standings_split_long_handle_lists() {
+    let body = format!(r#"{{"status": "OK", "result": {}}}"#, STANDINGS_JSON);
+    let response = |body: &str| {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    };
+    let (url, requests) = serve_raw(vec![response(&body), response(&body)]).await;
+    let client = Client::with_base_url(url);
+
+    let handles: Vec<_> = (0..=MAX_STANDINGS_HANDLES)
+        .map(|i| format!("user_{}", i))
+        .collect();
+    let (_, _, rows) = Contest::standings(&client, 1, |b| b.handles(handles.clone()))
+        .await
+        .unwrap();
+
+    {
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].contains("user_0%3B"));
+        assert!(!requests[0].contains("user_2000"));
+        assert!(requests[1].contains("handles=user_2000 HTTP"));
+    }
+    // Both batches returned the same parties, which are only kept once.
+    assert_eq!(rows.len(), standings_fixture().rows.len());
+    assert!(rows.windows(2).all(|w| w[0].rank <= w[1].rank));
+
+    // The limit applies to the merged rows, not to each batch.
+    let (url, requests) = serve_raw(vec![response(&body), response(&body)]).await;
+    let client = Client::with_base_url(url);
+    let (_, _, limited) = Contest::standings(&client, 1, |b| b.handles(handles).limit(2, 1))
+        .await
+        .unwrap();
+    assert!(requests
+        .lock()
+        .unwrap()
+        .iter()
+        .all(|r| !r.contains("from=") && !r.contains("count=")));
+    assert_eq!(limited.len(), 1);
+    assert_eq!(limited[0].party.name(), rows[1].party.name());
+}
+
+#[tokio::test]
+pub async fn standings_compact_splits_long_handle_lists() {
+    let body = format!(r#"{{"status": "OK", "result": {}}}"#, STANDINGS_JSON);
+    let response = |body: &str| {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    };
+    let (url, requests) = serve_raw(vec![response(&body), response(&body)]).await;
+    let client = Client::with_base_url(url);
+
+    let handles: Vec<_> = (0..=MAX_STANDINGS_HANDLES)
+        .map(|i| format!("user_{}", i))
+        .collect();
+    let compact = Contest::standings_compact(&client, 1, |b| b.handles(handles))
+        .await
+        .unwrap();
+    assert_eq!(requests.lock().unwrap().len(), 2);
+
+    let expected = standings_fixture();
+    assert_eq!(compact.rows.len(), expected.rows.len());
+    let back: Standings = compact.into();
+    for (got, want) in back.rows.iter().zip(&expected.rows) {
+        assert_eq!(got.party.name(), want.party.name());
+        assert_eq!(got.rank, want.rank);
+    }
+}
+
+#[test]
+pub fn rank_short_codes() {
+    let ranks = [
+        ("newbie", "N"),
+        ("pupil", "P"),
+        ("specialist", "S"),
+        ("expert", "E"),
+        ("candidate master", "CM"),
+        ("master", "M"),
+        ("international master", "IM"),
+        ("grandmaster", "GM"),
+        ("international grandmaster", "IGM"),
+        ("legendary grandmaster", "LGM"),
+    ];
+    for &(rank, code) in &ranks {
+        assert_eq!(rank_short_code(rank), Some(code), "{}", rank);
+    }
+    assert_eq!(rank_short_code("headquarters"), None);
+}
+
+#[tokio::test]
+pub async fn watch_standings_waits_between_failed_polls() {
+    use futures_util::StreamExt;
+    use std::time::{Duration, Instant};
+
+    // No response for the standings: every poll fails.
+    let http = Client::mock(std::collections::HashMap::new());
+    let start = Instant::now();
+    let polls: Vec<_> = Contest::watch_standings(&http, 1, Duration::from_millis(50))
+        .take(3)
+        .collect()
+        .await;
+    assert!(polls.iter().all(|p| p.is_err()));
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
+
+#[tokio::test]
+pub async fn responses_are_requested_compressed() {
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    let mut body = GzEncoder::new(Vec::new(), Compression::default());
+    body.write_all(br#"{"status": "OK", "result": []}"#)
+        .unwrap();
+    let body = body.finish().unwrap();
+    let mut response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )
+    .into_bytes();
+    response.extend(body);
+
+    let (url, requests) = serve_raw(vec![response]).await;
+    let http = Client::builder().base_url(url).build().unwrap();
+    assert!(User::rating(&http, "natsukagami").await.unwrap().is_empty());
+    let request = requests.lock().unwrap()[0].to_lowercase();
+    assert!(request.contains("accept-encoding: gzip"), "{}", request);
+
+    let (url, requests) = serve(vec![(200, r#"{"status": "OK", "result": []}"#)]).await;
+    let http = Client::builder()
+        .base_url(url)
+        .compression(false)
+        .build()
+        .unwrap();
+    User::rating(&http, "natsukagami").await.unwrap();
+    let request = requests.lock().unwrap()[0].to_lowercase();
+    assert!(!request.contains("accept-encoding"), "{}", request);
+}
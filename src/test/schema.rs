@@ -0,0 +1,153 @@
+//! Deserializes a sample of every object type, with all of its fields, to
+//! catch drift between the types and the API objects.
+//!
+//! The samples follow https://codeforces.com/apiHelp/objects.
+use super::*;
+
+/// Parses the sample `name` as a `T`.
+fn sample<T: for<'t> Deserialize<'t>>(name: &str, json: &str) -> T {
+    serde_json::from_str(json).unwrap_or_else(|e| panic!("{}: {}", name, e))
+}
+
+macro_rules! sample {
+    ($name:literal) => {
+        sample($name, include_str!(concat!("fixtures/", $name, ".json")))
+    };
+}
+
+#[test]
+pub fn user() {
+    let user: User = sample!("user");
+    assert_eq!(user.handle, "tourist");
+    assert_eq!(user.max_rating, Some(3979));
+    assert_eq!(user.last_online_time_seconds, Some(1700000000));
+}
+
+#[test]
+pub fn user_without_last_online_time() {
+    let user: User = sample!("user_deleted");
+    assert_eq!(user.handle, "deleted_user");
+    assert_eq!(user.last_online_time_seconds, None);
+}
+
+#[test]
+pub fn user_to_value() {
+    let user: User = sample!("user");
+    let value = user.to_value();
+    assert_eq!(value["handle"], "tourist");
+    assert_eq!(value["maxRating"], 3979);
+    assert!(value["registrationTimeSeconds"].is_u64());
+    // The value parses back into the same user.
+    let back: User = serde_json::from_value(value).unwrap();
+    assert_eq!(back.handle, user.handle);
+}
+
+#[test]
+pub fn rating_change() {
+    let change: RatingChange = sample!("rating_change");
+    assert_eq!(change.delta(), 1602);
+}
+
+#[test]
+pub fn rating_change_to_value() {
+    let change: RatingChange = sample!("rating_change");
+    let value = change.to_value();
+    assert_eq!(value["handle"], change.handle.as_str());
+    assert_eq!(value["newRating"], change.new_rating);
+}
+
+#[test]
+pub fn contest() {
+    let contest: Contest = sample!("contest");
+    assert_eq!(contest.contest_type, ContestType::ICPC);
+    assert_eq!(contest.difficulty, Some(4));
+    assert_eq!(contest.season.as_deref(), Some("2009-2010"));
+}
+
+#[test]
+pub fn problem() {
+    let problem: Problem = sample!("problem");
+    assert_eq!(problem.problem_type, ProblemType::Programming);
+    assert_eq!(problem.rating, Some(1000));
+}
+
+#[test]
+pub fn problem_statistics() {
+    let stats: ProblemStatistics = sample!("problem_statistics");
+    assert_eq!(stats.solved_count, 250000);
+}
+
+#[test]
+pub fn party() {
+    let party: Party = sample!("party");
+    assert_eq!(party.participant_type, ParticipantType::Virtual);
+    assert_eq!(party.members.len(), 2);
+}
+
+#[test]
+pub fn problem_result() {
+    let result: ProblemResult = sample!("problem_result");
+    assert_eq!(result.result_type, ProblemResultType::Preliminary);
+}
+
+#[test]
+pub fn ranklist_row() {
+    let row: RanklistRow = sample!("ranklist_row");
+    assert_eq!(row.problem_results.len(), 2);
+    assert_eq!(row.last_submission_time_seconds, Some(1800));
+}
+
+#[test]
+pub fn submission() {
+    let sub: Submission = sample!("submission");
+    assert_eq!(sub.verdict, Some(Verdict::Ok));
+    assert_eq!(sub.test_set, SubmissionTestSet::Tests);
+}
+
+#[test]
+pub fn submission_ioi() {
+    let sub: Submission = sample!("submission_ioi");
+    assert_eq!(sub.verdict, Some(Verdict::Partial));
+    assert_eq!(sub.points, Some(37.5));
+}
+
+#[test]
+pub fn contest_icpc_info() {
+    let mut contest: Contest = sample!("contest");
+    assert_eq!(
+        contest.icpc_info(),
+        Some(IcpcInfo {
+            region: "Europe".to_owned(),
+            country: "Russia".to_owned(),
+            city: "Petrozavodsk".to_owned(),
+            season: "2009-2010".to_owned(),
+        })
+    );
+
+    contest.city = None;
+    assert_eq!(contest.icpc_info(), None);
+    assert_eq!(standings_fixture().contest.icpc_info(), None);
+}
+
+#[test]
+pub fn hack() {
+    let hack: Hack = sample!("hack");
+    assert_eq!(hack.verdict, Some(HackVerdict::HackSuccessful));
+    assert_eq!(hack.defender.members[0].handle, "tourist");
+    assert!(hack.judge_protocol.unwrap().is_manual());
+}
+
+#[test]
+pub fn blog_entry() {
+    let entry: BlogEntry = sample!("blog_entry");
+    assert_eq!(entry.author_handle, "MikeMirzayanov");
+    assert!(entry.content.is_some());
+    assert_eq!(entry.tags.len(), 2);
+}
+
+#[test]
+pub fn comment() {
+    let comment: Comment = sample!("comment");
+    assert_eq!(comment.commentator_handle, "tourist");
+    assert_eq!(comment.parent_comment_id, Some(1121));
+}